@@ -0,0 +1,31 @@
+// Copyright 2015 Nick Fitzgerald
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reading the system clocks.
+
+use time;
+
+/// Get the number of nanoseconds elapsed since some arbitrary, fixed point in
+/// the past. Unlike the wall clock, this never goes backwards, and is suitable
+/// for timing things.
+pub fn monotonic_ns() -> u64 {
+    time::precise_time_ns()
+}
+
+/// Get the number of nanoseconds elapsed since the Unix epoch, according to
+/// the wall clock.
+pub fn realtime_ns() -> u64 {
+    let now = time::get_time();
+    (now.sec as u64) * 1_000_000_000 + (now.nsec as u64)
+}
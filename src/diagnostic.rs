@@ -0,0 +1,249 @@
+// Copyright 2015 Nick Fitzgerald
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compiler style diagnostics for reader and static analysis errors.
+//!
+//! A `Diagnostic` is an error message, the source location the error originates
+//! from, and any number of notes about related source locations. Its `Display`
+//! implementation is the terse `file:line:column: message` form used within
+//! error strings, while `Diagnostic::render` quotes the offending source and
+//! underlines the relevant span:
+//!
+//!     error: Static error: can only define symbols, found: 5
+//!      --> example.scm:1:9
+//!       |
+//!     1 | (define 5 6)
+//!       |         ^
+//!     note: in this definition
+//!      --> example.scm:1:1
+//!       |
+//!     1 | (define 5 6)
+//!       | ^^^^^^^^^^^^
+
+use std::cmp;
+use std::error::{FromError};
+use std::fmt;
+use std::iter;
+use std::old_io::{File};
+
+use read::{Location};
+
+/// The ANSI escape code for the colour of error labels and underlines.
+static ERROR_COLOUR : &'static str = "\x1b[1;31m";
+
+/// The ANSI escape code for the colour of note labels and underlines.
+static NOTE_COLOUR : &'static str = "\x1b[1;36m";
+
+/// The ANSI escape code to reset the colour.
+static RESET_COLOUR : &'static str = "\x1b[0m";
+
+/// An error message, the location it originates from, and notes about related
+/// locations.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    /// The location of the error.
+    pub location: Location,
+    /// The error message.
+    pub message: String,
+    /// Related locations, each with a message explaining its relevance.
+    pub notes: Vec<(Location, String)>,
+}
+
+impl Diagnostic {
+    /// Create a new `Diagnostic` without any notes.
+    pub fn new(location: Location, message: String) -> Diagnostic {
+        Diagnostic {
+            location: location,
+            message: message,
+            notes: vec!(),
+        }
+    }
+
+    /// Add a note about a related location to this diagnostic.
+    pub fn with_note(mut self, location: Location, message: String) -> Diagnostic {
+        self.notes.push((location, message));
+        self
+    }
+
+    /// Render this diagnostic and its notes, quoting the source line of each
+    /// location and underlining the span. If `colour` is true, the labels and
+    /// underlines are coloured with ANSI escape codes.
+    pub fn render(&self, colour: bool) -> String {
+        let mut out = String::new();
+        render_part(&mut out, "error", ERROR_COLOUR, &self.location,
+                    self.message.as_slice(), colour);
+        for &(ref location, ref message) in self.notes.iter() {
+            render_part(&mut out, "note", NOTE_COLOUR, location,
+                        message.as_slice(), colour);
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// Print the diagnostic as `file:line:column: message`, or just the message
+    /// if its location is unknown.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.location.is_unknown() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.location, self.message)
+        }
+    }
+}
+
+impl FromError<String> for Diagnostic {
+    /// Errors that do not have a location, such as those from `Value::cadr`,
+    /// become diagnostics with an unknown location.
+    fn from_error(message: String) -> Diagnostic {
+        Diagnostic::new(Location::unknown(), message)
+    }
+}
+
+/// Render a single labeled message, and the source line it refers to, onto the
+/// end of `out`.
+fn render_part(out: &mut String,
+               label: &str,
+               label_colour: &str,
+               location: &Location,
+               message: &str,
+               colour: bool) {
+    let (start, reset) = if colour {
+        (label_colour, RESET_COLOUR)
+    } else {
+        ("", "")
+    };
+
+    out.push_str(format!("{}{}:{} {}\n", start, label, reset, message).as_slice());
+    if location.is_unknown() {
+        return;
+    }
+
+    let gutter = location.line.to_string();
+    let padding : String = iter::repeat(' ').take(gutter.len()).collect();
+    out.push_str(format!("{}--> {}\n", padding, location).as_slice());
+
+    // Only quote source that was read from a file: a pseudo file name such
+    // as `stdin` might also happen to name an unrelated file on disk.
+    if !location.is_file {
+        return;
+    }
+    let line = match source_line(location.file.as_slice(), location.line) {
+        Some(line) => line,
+        None       => return,
+    };
+
+    // Underline the span up to the end of its first line, and always underline
+    // at least one column so that spans at the end of the line are visible.
+    let first = (location.column - 1) as usize;
+    let last = if location.end_line > location.line {
+        line.chars().count()
+    } else {
+        (location.end_column - 1) as usize
+    };
+    let last = cmp::max(last, first + 1);
+
+    // Preserve tabs so that the underline lines up with the source.
+    let indent : String = line.chars()
+        .chain(iter::repeat(' '))
+        .take(first)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let underline : String = iter::repeat('^').take(last - first).collect();
+
+    out.push_str(format!("{} |\n", padding).as_slice());
+    out.push_str(format!("{} | {}\n", gutter, line).as_slice());
+    out.push_str(format!("{} | {}{}{}{}\n",
+                         padding, indent, start, underline, reset).as_slice());
+}
+
+/// Get the given 1-based line of the given source file, or `None` if the file
+/// cannot be read, e.g. because it has since been deleted.
+fn source_line(file: &str, line: u64) -> Option<String> {
+    let source = match File::open(&Path::new(file)).read_to_string() {
+        Ok(source) => source,
+        Err(_)     => return None,
+    };
+
+    source.lines()
+        .nth((line - 1) as usize)
+        .map(|l| l.to_string())
+}
+
+// TESTS -----------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use read::{Location};
+
+    fn location(file: &str, line: u64, column: u64, end_column: u64) -> Location {
+        let mut loc = Location::new(file.to_string());
+        loc.is_file = true;
+        loc.line = line;
+        loc.column = column;
+        loc.end_line = line;
+        loc.end_column = end_column;
+        loc
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = Diagnostic::new(location("foo.scm", 1, 9, 10),
+                                         "Oops".to_string());
+        assert_eq!(format!("{}", diagnostic), "foo.scm:1:9: Oops");
+
+        let diagnostic = Diagnostic::new(Location::unknown(), "Oops".to_string());
+        assert_eq!(format!("{}", diagnostic), "Oops");
+    }
+
+    #[test]
+    fn test_diagnostic_render() {
+        let file = "./tests/test_eval_define_non_symbol.scm";
+        let diagnostic = Diagnostic::new(location(file, 1, 9, 10),
+                                         "can only define symbols".to_string())
+            .with_note(location(file, 1, 1, 13), "in this definition".to_string());
+        assert_eq!(diagnostic.render(false),
+                   format!("error: can only define symbols\n \
+                            --> {}:1:9\n  \
+                            |\n\
+                            1 | (define 5 6)\n  \
+                            |         ^\n\
+                            note: in this definition\n \
+                            --> {}:1:1\n  \
+                            |\n\
+                            1 | (define 5 6)\n  \
+                            | ^^^^^^^^^^^^\n", file, file));
+    }
+
+    #[test]
+    fn test_diagnostic_render_without_source() {
+        let diagnostic = Diagnostic::new(location("stdin", 3, 2, 2),
+                                         "Unexpected EOF".to_string());
+        assert_eq!(diagnostic.render(true),
+                   "\x1b[1;31merror:\x1b[0m Unexpected EOF\n --> stdin:3:2\n");
+    }
+
+    #[test]
+    fn test_diagnostic_render_pseudo_file() {
+        // Even though the name happens to be a real file, the source didn't
+        // come from it, so it must not be quoted.
+        let file = "./tests/test_eval_define_non_symbol.scm";
+        let mut loc = location(file, 1, 9, 10);
+        loc.is_file = false;
+        let diagnostic = Diagnostic::new(loc, "can only define symbols".to_string());
+        assert_eq!(diagnostic.render(false),
+                   format!("error: can only define symbols\n --> {}:1:9\n", file));
+    }
+}
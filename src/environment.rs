@@ -248,6 +248,11 @@ impl Environment {
         return None;
     }
 
+    /// Get the names of all of the global variables.
+    pub fn global_names<'a>(&'a self) -> Vec<&'a String> {
+        self.bindings[0].keys().collect()
+    }
+
     fn youngest<'a>(&'a mut self) -> &'a mut HashMap<String, u32> {
         let last_idx = self.bindings.len() - 1;
         &mut self.bindings[last_idx]
@@ -69,13 +69,21 @@ use std::fmt;
 use std::hash;
 
 use environment::{Activation, RootedActivationPtr};
+use diagnostic::{Diagnostic};
 use heap::{Heap, Rooted};
+use primitives;
 use read::{Location};
-use value::{RootedValue, SchemeResult, Value};
+use record::{Record};
+use value::{self, RootedConsPtr, RootedValue, SchemeResult, Value};
 
 /// Evaluate the given form in the global environment.
 pub fn evaluate(heap: &mut Heap, form: &RootedValue, location: Location) -> SchemeResult {
-    let meaning = try!(analyze(heap, form, location));
+    let meaning = try!(analyze(heap, form, location).map_err(|d| format!("{}", d)));
+    evaluate_meaning(heap, &meaning)
+}
+
+/// Evaluate the given, already analyzed, form in the global environment.
+pub fn evaluate_meaning(heap: &mut Heap, meaning: &Meaning) -> SchemeResult {
     let mut act = heap.global_activation();
     meaning.evaluate(heap, &mut act)
 }
@@ -457,8 +465,11 @@ impl Meaning {
                          act: &mut RootedActivationPtr) -> TrampolineResult {
         match (self.evaluator)(heap, &*self.data, act) {
             // Add this location to the error message. These stack up and give a
-            // backtrace.
-            Err(e) => Err(format!("{}:\n{}", self.location, e)),
+            // backtrace. Keep the bare message too, for `guard`.
+            Err(e) => {
+                heap.note_error_message(e.as_slice());
+                Err(format!("{}:\n{}", self.location, e))
+            },
             ok => ok
         }
     }
@@ -498,7 +509,7 @@ impl hash::Hash for Meaning {
 }
 
 /// Either a `Meaning`, or a `String` explaining the error.
-pub type MeaningResult = Result<Meaning, String>;
+pub type MeaningResult = Result<Meaning, Diagnostic>;
 
 /// The main entry point for syntactic analysis.
 pub fn analyze(heap: &mut Heap,
@@ -517,15 +528,19 @@ pub fn analyze(heap: &mut Heap,
     let define = heap.define_symbol();
     let set_bang = heap.set_bang_symbol();
     let lambda = heap.lambda_symbol();
+    let define_record_type = heap.define_record_type_symbol();
+    let guard = heap.guard_symbol();
 
     match *pair.car(heap) {
-        v if v == *quote     => analyze_quoted(heap, form),
-        v if v == *define    => analyze_definition(heap, form),
-        v if v == *set_bang  => analyze_set(heap, form),
-        v if v == *lambda    => analyze_lambda(heap, form),
-        v if v == *if_symbol => analyze_conditional(heap, form),
-        v if v == *begin     => analyze_sequence(heap, form),
-        _                    => analyze_invocation(heap, form),
+        v if v == *quote              => analyze_quoted(heap, form),
+        v if v == *define             => analyze_definition(heap, form),
+        v if v == *set_bang           => analyze_set(heap, form),
+        v if v == *lambda             => analyze_lambda(heap, form),
+        v if v == *if_symbol          => analyze_conditional(heap, form),
+        v if v == *begin              => analyze_sequence(heap, form),
+        v if v == *define_record_type => analyze_record_type_definition(heap, form),
+        v if v == *guard              => analyze_guard(heap, form),
+        _                             => analyze_invocation(heap, form),
     }
 }
 
@@ -558,7 +573,31 @@ fn analyze_atom(heap: &mut Heap,
         return Ok(Meaning::new_reference(i, j, (**sym).clone(), location));
     }
 
-    return Err(format!("Static error: Cannot evaluate: {}", **form));
+    return Err(Diagnostic::new(location,
+                               format!("Static error: Cannot evaluate: {}", **form)));
+}
+
+/// Create a diagnostic for a static error in the given form, located at the
+/// form itself.
+fn static_error(heap: &mut Heap, form: &RootedValue, msg: &str) -> Diagnostic {
+    let location = form.to_pair(heap)
+        .map(|pair| heap.locate(&pair))
+        .unwrap_or_else(Location::unknown);
+    Diagnostic::new(location, format!("{}: {}", msg, **form))
+}
+
+/// Get the source location of the `n`th element of the given list form, so that
+/// errors can point at the exact sub-expression. Falls back to the location of
+/// the whole form if the list is too short.
+fn locate_nth(heap: &mut Heap, form: &RootedConsPtr, n: usize) -> Location {
+    let mut cons = form.clone();
+    for _ in range(0, n) {
+        match cons.cdr(heap).to_pair(heap) {
+            Some(next) => cons = next,
+            None       => return heap.locate(form),
+        }
+    }
+    heap.locate_car(&cons)
 }
 
 fn analyze_quoted(heap: &mut Heap, form: &RootedValue) -> MeaningResult {
@@ -569,12 +608,11 @@ fn analyze_quoted(heap: &mut Heap, form: &RootedValue) -> MeaningResult {
             heap.locate(&pair)));
     }
 
-    let msg = "Static error: Wrong number of parts in quoted form";
-    Err(if let Some(pair) = form.to_pair(heap) {
-        format!("{}: {}", heap.locate(&pair), msg)
-    } else {
-        msg.to_string()
-    })
+    let location = form.to_pair(heap)
+        .map(|pair| heap.locate(&pair))
+        .unwrap_or_else(Location::unknown);
+    Err(Diagnostic::new(location,
+                        "Static error: Wrong number of parts in quoted form".to_string()))
 }
 
 fn analyze_definition(heap: &mut Heap,
@@ -588,25 +626,23 @@ fn analyze_definition(heap: &mut Heap,
 
         if let Some(str) = sym.to_symbol(heap) {
             let def_value_form = try!(pair.caddr(heap));
+            let def_value_location = locate_nth(heap, &pair, 2);
             let def_value_meaning = try!(analyze(heap,
                                                  &def_value_form,
-                                                 location.clone()));
+                                                 def_value_location));
 
             let (i, j) = heap.environment.define((**str).clone());
             return Ok(Meaning::new_definition(i, j, def_value_meaning, location));
         }
 
-        return Err(format!("{}: Static error: can only define symbols, found: {}",
-                           location,
-                           *sym));
+        let diagnostic = Diagnostic::new(
+            locate_nth(heap, &pair, 1),
+            format!("Static error: can only define symbols, found: {}", *sym));
+        return Err(diagnostic.with_note(location,
+                                        "in this definition".to_string()));
     }
 
-    let msg = "Static error: improperly formed definition";
-    Err(if let Some(pair) = form.to_pair(heap) {
-        format!("{}: {}: {}", heap.locate(&pair), msg, **form)
-    } else {
-        format!("{}: {}", msg, **form)
-    })
+    Err(static_error(heap, form, "Static error: improperly formed definition"))
 }
 
 fn analyze_set(heap: &mut Heap,
@@ -620,9 +656,10 @@ fn analyze_set(heap: &mut Heap,
 
         if let Some(str) = sym.to_symbol(heap) {
             let set_value_form = try!(pair.caddr(heap));
+            let set_value_location = locate_nth(heap, &pair, 2);
             let set_value_meaning = try!(analyze(heap,
                                                  &set_value_form,
-                                                 location.clone()));
+                                                 set_value_location));
             if let Some((i, j)) = heap.environment.lookup(&**str) {
                 return Ok(Meaning::new_set_variable(i,
                                                     j,
@@ -639,37 +676,24 @@ fn analyze_set(heap: &mut Heap,
                                                 location));
         }
 
-        return Err(format!("{}: Static error: can only set! symbols, found: {}",
-                           location,
-                           *sym));
+        let diagnostic = Diagnostic::new(
+            locate_nth(heap, &pair, 1),
+            format!("Static error: can only set! symbols, found: {}", *sym));
+        return Err(diagnostic.with_note(location,
+                                        "in this set! expression".to_string()));
     }
 
-    let msg = "Static error: improperly formed set!";
-    Err(if let Some(pair) = form.to_pair(heap) {
-        format!("{}: {}: {}", heap.locate(&pair), msg, **form)
-    } else {
-        format!("{}: {}", msg, **form)
-    })
+    Err(static_error(heap, form, "Static error: improperly formed set!"))
 }
 
 fn analyze_lambda(heap: &mut Heap,
                   form: &RootedValue) -> MeaningResult {
     let length = try!(form.len().ok().ok_or_else(|| {
-        let msg = "Static error: improperly formed lambda";
-        if let Some(pair) = form.to_pair(heap) {
-            format!("{}: {}: {}", heap.locate(&pair), msg, **form)
-        } else {
-            format!("{}: {}", msg, **form)
-        }
+        static_error(heap, form, "Static error: improperly formed lambda")
     }));
 
     if length < 3 {
-        let msg = "Static error: improperly formed lambda";
-        return Err(if let Some(pair) = form.to_pair(heap) {
-            format!("{}: {}: {}", heap.locate(&pair), msg, **form)
-        } else {
-            format!("{}: {}", msg, **form)
-        })
+        return Err(static_error(heap, form, "Static error: improperly formed lambda"));
     }
 
     let pair = form.to_pair(heap).unwrap();
@@ -682,39 +706,58 @@ fn analyze_lambda(heap: &mut Heap,
     let mut arity = 0;
     let params_form = pair.cadr(heap).ok().expect(
         "Must be here since length >= 3");
+    let params_location = locate_nth(heap, &pair, 1);
     for p in params_form.iter() {
         arity += 1;
-        params.push(try!(p.ok().ok_or(format!("{}: Bad lambda parameters: {}",
-                                              location,
-                                              *params_form))));
+        params.push(try!(p.ok().ok_or_else(|| {
+            Diagnostic::new(params_location.clone(),
+                            format!("Bad lambda parameters: {}", *params_form))
+                .with_note(location.clone(), "in this lambda".to_string())
+        })));
     }
 
-    let mut param_names : Vec<String> = try!(params.into_iter().map(|p| {
-        let sym = try!(p.to_symbol(heap)
-                       .ok_or(format!("{}: Can only define symbol parameters, found {}",
-                                      location,
-                                      p)));
-        Ok((**sym).clone())
-    }).collect());
+    let mut param_names : Vec<String> = Vec::with_capacity(params.len());
+    for (n, p) in params.into_iter().enumerate() {
+        match p.to_symbol(heap) {
+            Some(sym) => param_names.push((**sym).clone()),
+            None      => {
+                let params_pair = params_form.to_pair(heap).expect(
+                    "Must be a pair since there is at least one parameter");
+                let diagnostic = Diagnostic::new(
+                    locate_nth(heap, &params_pair, n),
+                    format!("Can only define symbol parameters, found {}", p));
+                return Err(diagnostic.with_note(location,
+                                                "in this lambda".to_string()));
+            },
+        }
+    }
 
     // Find any definitions in the body, so we can add them to the extended
     // environment.
     let define = heap.define_symbol();
-    let mut local_definitions : Vec<String> = body.iter()
-        .filter_map(|form_result| {
-            if let Ok(form) = form_result {
-                if let Some(pair) = form.to_pair(heap) {
-                    if pair.car(heap) == define {
-                        if let Ok(name) = pair.cadr(heap) {
-                            return name.to_symbol(heap).map(|s| (**s).clone())
-                        }
-                    }
+    let define_record_type = heap.define_record_type_symbol();
+    let mut local_definitions : Vec<String> = vec!();
+    for form_result in body.iter() {
+        let form = match form_result {
+            Ok(form) => Rooted::new(heap, form),
+            Err(_)   => continue,
+        };
+        if let Some(pair) = form.to_pair(heap) {
+            let head = pair.car(heap);
+            if head == define {
+                if let Ok(name) = pair.cadr(heap) {
+                    local_definitions.extend(
+                        name.to_symbol(heap).map(|s| (**s).clone()).into_iter());
+                }
+            } else if head == define_record_type {
+                // Malformed definitions are reported when the body itself is
+                // analyzed.
+                if let Ok(definition) = RecordTypeDefinition::parse(heap, &form) {
+                    local_definitions.extend(definition.defined_names().into_iter());
                 }
             }
-
-            None
-        })
-        .collect();
+        }
+    }
 
     let mut new_bindings = Vec::with_capacity(param_names.len() + local_definitions.len());
     new_bindings.append(&mut param_names);
@@ -735,19 +778,22 @@ fn analyze_conditional(heap: &mut Heap,
         let location = heap.locate(&pair);
 
         let condition_form = try!(pair.cadr(heap));
+        let condition_location = locate_nth(heap, &pair, 1);
         let condition_meaning = try!(analyze(heap,
                                              &condition_form,
-                                             location.clone()));
+                                             condition_location));
 
         let consequent_form = try!(pair.caddr(heap));
+        let consequent_location = locate_nth(heap, &pair, 2);
         let consequent_meaning = try!(analyze(heap,
                                               &consequent_form,
-                                              location.clone()));
+                                              consequent_location));
 
         let alternative_form = try!(pair.cadddr(heap));
+        let alternative_location = locate_nth(heap, &pair, 3);
         let alternative_meaning = try!(analyze(heap,
                                                &alternative_form,
-                                               location.clone()));
+                                               alternative_location));
 
         return Ok(Meaning::new_conditional(condition_meaning,
                                            consequent_meaning,
@@ -755,12 +801,7 @@ fn analyze_conditional(heap: &mut Heap,
                                            location));
     }
 
-    let msg = "Static error: improperly if expression";
-    Err(if let Some(pair) = form.to_pair(heap) {
-        format!("{}: {}: {}", heap.locate(&pair), msg, **form)
-    } else {
-        format!("{}: {}", msg, **form)
-    })
+    Err(static_error(heap, form, "Static error: improperly if expression"))
 }
 
 fn make_meaning_sequence(heap: &mut Heap,
@@ -768,7 +809,8 @@ fn make_meaning_sequence(heap: &mut Heap,
     if let Some(ref cons) = forms.to_pair(heap) {
         let first_form = cons.car(heap);
         let location = heap.locate(cons);
-        let first = try!(analyze(heap, &first_form, location.clone()));
+        let first_location = heap.locate_car(cons);
+        let first = try!(analyze(heap, &first_form, first_location));
 
         if *cons.cdr(heap) == Value::EmptyList {
             return Ok(first);
@@ -779,26 +821,27 @@ fn make_meaning_sequence(heap: &mut Heap,
         }
     }
 
-    Err(format!("Static error: improperly formed sequence: {}", **forms))
+    Err(static_error(heap, forms, "Static error: improperly formed sequence"))
 }
 
 fn analyze_sequence(heap: &mut Heap,
                     form: &RootedValue) -> MeaningResult {
-    let forms = try!(form.cdr(heap).ok_or(
-        format!("Static error: improperly formed sequence: {}", **form)));
+    let forms = try!(form.cdr(heap).ok_or_else(|| {
+        static_error(heap, form, "Static error: improperly formed sequence")
+    }));
     make_meaning_sequence(heap, &forms)
 }
 
 fn make_meaning_vector(heap: &mut Heap,
                        forms: &RootedValue,
-                       mut meanings: Vec<Meaning>) -> Result<Vec<Meaning>, String> {
+                       mut meanings: Vec<Meaning>) -> Result<Vec<Meaning>, Diagnostic> {
     match **forms {
         Value::EmptyList => Ok(meanings),
         Value::Pair(ref cons) => {
             let car = cons.car(heap);
             let rest = cons.cdr(heap);
             let pair = forms.to_pair(heap).unwrap();
-            let location = heap.locate(&pair);
+            let location = heap.locate_car(&pair);
             meanings.push(try!(analyze(heap,
                                        &car,
                                        location)));
@@ -810,23 +853,408 @@ fn make_meaning_vector(heap: &mut Heap,
     }
 }
 
+/// The parts of a `define-record-type` form.
+struct RecordTypeDefinition {
+    type_name: String,
+    field_names: Vec<String>,
+    /// The constructor's name, and the index of the field that each of its
+    /// parameters initializes.
+    constructor: Option<(String, Vec<usize>)>,
+    predicate: Option<String>,
+    /// The name and field index of each accessor.
+    accessors: Vec<(String, usize)>,
+    /// The name and field index of each modifier.
+    modifiers: Vec<(String, usize)>,
+}
+
+impl RecordTypeDefinition {
+    /// Parse a `define-record-type` form:
+    ///
+    ///     (define-record-type <name> (constructor field ...) predicate
+    ///       (field accessor [modifier]) ...)
+    ///
+    /// The constructor may also be a bare name, in which case it takes every
+    /// field in order, and either the constructor or the predicate may be `#f`
+    /// to leave it undefined.
+    fn parse(heap: &mut Heap,
+             form: &RootedValue) -> Result<RecordTypeDefinition, Diagnostic> {
+        let malformed = "Static error: improperly formed define-record-type";
+
+        let parts : Vec<RootedValue> = match form.iter().collect::<Result<Vec<Value>, ()>>() {
+            Ok(ref parts) if parts.len() >= 4 => {
+                parts.iter().map(|part| Rooted::new(heap, *part)).collect()
+            },
+            _                                 => return Err(static_error(heap, form, malformed)),
+        };
+
+        let type_name = try!(symbol_name(&*parts[1]).ok_or_else(|| {
+            static_error(heap, form, malformed)
+        }));
+
+        let mut field_names : Vec<String> = vec!();
+        let mut accessors = vec!();
+        let mut modifiers = vec!();
+        for (i, field_spec) in parts.slice_from(4).iter().enumerate() {
+            let names = match symbol_names(&**field_spec) {
+                Some(ref names) if names.len() == 2 || names.len() == 3 => names.clone(),
+                _                                                       => {
+                    return Err(static_error(heap, field_spec,
+                                            "Static error: improperly formed record field"));
+                },
+            };
+            if field_names.contains(&names[0]) {
+                return Err(static_error(heap, field_spec,
+                                        "Static error: duplicate record field"));
+            }
+            field_names.push(names[0].clone());
+            accessors.push((names[1].clone(), i));
+            if let Some(modifier) = names.get(2) {
+                modifiers.push((modifier.clone(), i));
+            }
+        }
+
+        let constructor = match *parts[2] {
+            Value::Boolean(false) => None,
+            Value::Symbol(name)   => Some(((*name).clone(), range(0, field_names.len()).collect())),
+            _                     => {
+                let names = try!(symbol_names(&*parts[2])
+                    .and_then(|names| if names.len() > 0 { Some(names) } else { None })
+                    .ok_or_else(|| static_error(heap, &parts[2], malformed)));
+                let mut indices = vec!();
+                for field in names.slice_from(1).iter() {
+                    match field_names.iter().position(|f| f == field) {
+                        Some(i) if !indices.contains(&i) => indices.push(i),
+                        _                                => {
+                            return Err(static_error(
+                                heap, &parts[2],
+                                "Static error: constructor parameters must be distinct record fields"));
+                        },
+                    }
+                }
+                Some((names[0].clone(), indices))
+            },
+        };
+
+        let predicate = match *parts[3] {
+            Value::Boolean(false) => None,
+            Value::Symbol(name)   => Some((*name).clone()),
+            _                     => return Err(static_error(heap, form, malformed)),
+        };
+
+        Ok(RecordTypeDefinition {
+            type_name: type_name,
+            field_names: field_names,
+            constructor: constructor,
+            predicate: predicate,
+            accessors: accessors,
+            modifiers: modifiers,
+        })
+    }
+
+    /// Get the names of all the variables this form defines.
+    fn defined_names(&self) -> Vec<String> {
+        let mut names = vec!(self.type_name.clone());
+        names.extend(self.constructor.iter().map(|&(ref name, _)| name.clone()));
+        names.extend(self.predicate.iter().map(|name| name.clone()));
+        names.extend(self.accessors.iter()
+                     .chain(self.modifiers.iter())
+                     .map(|&(ref name, _)| name.clone()));
+        names
+    }
+}
+
+/// Get the name of the given symbol, or `None` if it isn't a symbol.
+fn symbol_name(val: &Value) -> Option<String> {
+    match *val {
+        Value::Symbol(name) => Some((*name).clone()),
+        _                   => None,
+    }
+}
+
+/// Get the names of the symbols in the given list, or `None` if it isn't a
+/// proper list of symbols.
+fn symbol_names(val: &Value) -> Option<Vec<String>> {
+    val.iter()
+        .map(|part| part.ok().and_then(|part| symbol_name(&part)))
+        .collect()
+}
+
+/// Make the form `(define name val)`.
+fn make_definition_form(heap: &mut Heap, name: &str, val: &RootedValue) -> RootedValue {
+    let define = heap.define_symbol();
+    let name = heap.get_or_create_symbol(name.to_string());
+    value::list(heap, &[define, name, val.clone()])
+}
+
+/// Make the form `(lambda (param ...) (body ...))`.
+fn make_lambda_form(heap: &mut Heap,
+                    params: &[RootedValue],
+                    body: &[RootedValue]) -> RootedValue {
+    let lambda = heap.lambda_symbol();
+    let params = value::list(heap, params);
+    let body = value::list(heap, body);
+    value::list(heap, &[lambda, params, body])
+}
+
+/// Analyze a `define-record-type` form by expanding it into definitions of the
+/// record type and its procedures, and analyzing those. The record type itself
+/// is created now, and it and the record primitives are embedded in the
+/// expansion as constants, so that the expansion can't be affected by
+/// shadowing. For example,
+///
+///     (define-record-type <point> (make-point x y) point?
+///       (x point-x set-point-x!)
+///       (y point-y))
+///
+/// expands into
+///
+///     (begin
+///       (define <point> #<record-type point>)
+///       (define make-point
+///         (lambda (x y) (#<make-record> #<record-type point> x y)))
+///       (define point?
+///         (lambda (obj) (#<record?> #<record-type point> obj)))
+///       (define point-x
+///         (lambda (record) (#<record-ref> #<record-type point> 0 'point-x record)))
+///       (define point-y
+///         (lambda (record) (#<record-ref> #<record-type point> 1 'point-y record)))
+///       (define set-point-x!
+///         (lambda (record value)
+///           (#<record-set!> #<record-type point> 0 'set-point-x! record value))))
+fn analyze_record_type_definition(heap: &mut Heap,
+                                  form: &RootedValue) -> MeaningResult {
+    let definition = try!(RecordTypeDefinition::parse(heap, form));
+    let pair = form.to_pair(heap).expect("Must be a pair since it parsed");
+    let location = heap.locate(&pair);
+
+    let record_type = Value::new_record(heap, Record::new_type(definition.type_name.clone(),
+                                                               definition.field_names.clone()));
+    let make_record = Rooted::new(heap, Value::new_primitive("make-record",
+                                                             primitives::make_record));
+    let record_question = Rooted::new(heap, Value::new_primitive("record?",
+                                                                 primitives::record_question));
+    let record_ref = Rooted::new(heap, Value::new_primitive("record-ref",
+                                                            primitives::record_ref));
+    let record_set_bang = Rooted::new(heap, Value::new_primitive("record-set!",
+                                                                 primitives::record_set_bang));
+    let quote = heap.quote_symbol();
+    let obj = heap.get_or_create_symbol("obj".to_string());
+    let record = heap.get_or_create_symbol("record".to_string());
+    let val = heap.get_or_create_symbol("value".to_string());
+
+    let mut forms = vec!(heap.begin_symbol());
+    forms.push(make_definition_form(heap, definition.type_name.as_slice(), &record_type));
+
+    if let Some((ref name, ref indices)) = definition.constructor {
+        let mut params = vec!();
+        for i in indices.iter() {
+            params.push(heap.get_or_create_symbol(definition.field_names[*i].clone()));
+        }
+        let mut body = vec!(make_record.clone(), record_type.clone());
+        for i in range(0, definition.field_names.len()) {
+            match indices.iter().position(|j| *j == i) {
+                Some(k) => body.push(params[k].clone()),
+                None    => body.push(Rooted::new(heap, Value::new_boolean(false))),
+            }
+        }
+        let lambda = make_lambda_form(heap, params.as_slice(), body.as_slice());
+        forms.push(make_definition_form(heap, name.as_slice(), &lambda));
+    }
+
+    if let Some(ref name) = definition.predicate {
+        let lambda = make_lambda_form(heap,
+                                      &[obj.clone()],
+                                      &[record_question.clone(), record_type.clone(), obj.clone()]);
+        forms.push(make_definition_form(heap, name.as_slice(), &lambda));
+    }
+
+    for &(ref name, i) in definition.accessors.iter() {
+        let index = Rooted::new(heap, Value::new_integer(i as i64));
+        let name_symbol = heap.get_or_create_symbol(name.clone());
+        let quoted_name = value::list(heap, &[quote.clone(), name_symbol]);
+        let lambda = make_lambda_form(heap,
+                                      &[record.clone()],
+                                      &[record_ref.clone(), record_type.clone(), index,
+                                        quoted_name, record.clone()]);
+        forms.push(make_definition_form(heap, name.as_slice(), &lambda));
+    }
+
+    for &(ref name, i) in definition.modifiers.iter() {
+        let index = Rooted::new(heap, Value::new_integer(i as i64));
+        let name_symbol = heap.get_or_create_symbol(name.clone());
+        let quoted_name = value::list(heap, &[quote.clone(), name_symbol]);
+        let lambda = make_lambda_form(heap,
+                                      &[record.clone(), val.clone()],
+                                      &[record_set_bang.clone(), record_type.clone(), index,
+                                        quoted_name, record.clone(), val.clone()]);
+        forms.push(make_definition_form(heap, name.as_slice(), &lambda));
+    }
+
+    // Attribute the whole expansion to the original form, so that errors in it
+    // point somewhere sensible.
+    for expanded in forms.iter() {
+        if let Some(expanded_pair) = expanded.to_pair(heap) {
+            heap.enlocate(location.clone(), expanded_pair);
+        }
+    }
+    let expansion = value::list(heap, forms.as_slice());
+    if let Some(expansion_pair) = expansion.to_pair(heap) {
+        heap.enlocate(location, expansion_pair);
+    }
+
+    analyze_sequence(heap, &expansion)
+}
+
+/// Analyze a `guard` form by expanding it into a call to the `guard` primitive
+/// with a thunk for the body and a handler procedure for the clauses, which
+/// takes the condition as the guard's variable. The clauses become a chain of
+/// `if`s, ending with re-raising the condition when no clause applies. As with
+/// `define-record-type`, the primitives are embedded in the expansion as
+/// constants. For example,
+///
+///     (guard (e ((file-error? e) 'missing)
+///               ((assq 'a e) => cdr)
+///               ((symbol? e)))
+///       (open-input-file "data.txt"))
+///
+/// expands into
+///
+///     (#<guard>
+///      (lambda () (open-input-file "data.txt"))
+///      (lambda (e)
+///        (if (file-error? e)
+///            (begin 'missing)
+///            (#<guard-clause> (assq 'a e) cdr
+///              (lambda ()
+///                (#<guard-clause> (symbol? e) #f
+///                  (lambda () (#<raise-continuable> e))))))))
+fn analyze_guard(heap: &mut Heap,
+                 form: &RootedValue) -> MeaningResult {
+    let malformed = "Static error: improperly formed guard";
+    let pair = form.to_pair(heap).expect("Must be a pair since it is a guard form");
+    let location = heap.locate(&pair);
+
+    let parts : Vec<RootedValue> = match form.iter().collect::<Result<Vec<Value>, ()>>() {
+        Ok(ref parts) if parts.len() >= 3 => {
+            parts.iter().map(|part| Rooted::new(heap, *part)).collect()
+        },
+        _                                 => return Err(static_error(heap, form, malformed)),
+    };
+    let spec : Vec<RootedValue> = match parts[1].iter().collect::<Result<Vec<Value>, ()>>() {
+        Ok(ref spec) if spec.len() >= 1 => {
+            spec.iter().map(|part| Rooted::new(heap, *part)).collect()
+        },
+        _                               => return Err(static_error(heap, form, malformed)),
+    };
+    if symbol_name(&*spec[0]).is_none() {
+        return Err(static_error(heap, form, malformed));
+    }
+    let var = spec[0].clone();
+
+    let raise_continuable = Rooted::new(heap, Value::new_primitive(
+        "raise-continuable", primitives::raise_continuable));
+    let mut handler_body = value::list(heap, &[raise_continuable, var.clone()]);
+
+    // Build the chain of clauses from the last one back to the first.
+    let clauses = spec.slice_from(1);
+    for (i, clause) in clauses.iter().enumerate().rev() {
+        let clause_parts : Vec<RootedValue> =
+            match clause.iter().collect::<Result<Vec<Value>, ()>>() {
+                Ok(ref clause_parts) if clause_parts.len() >= 1 => {
+                    clause_parts.iter().map(|part| Rooted::new(heap, *part)).collect()
+                },
+                _                                               => {
+                    return Err(static_error(heap, clause,
+                                            "Static error: improperly formed guard clause"));
+                },
+            };
+        let test = clause_parts[0].clone();
+        let exprs = clause_parts.slice_from(1);
+
+        let mut begin = vec!(heap.begin_symbol());
+        begin.push_all(exprs);
+
+        let is_else = symbol_name(&*test).map_or(false, |name| name.as_slice() == "else");
+        let is_arrow = exprs.get(0)
+            .and_then(|expr| symbol_name(&**expr))
+            .map_or(false, |name| name.as_slice() == "=>");
+
+        handler_body = if is_else {
+            if i != clauses.len() - 1 || exprs.len() == 0 {
+                return Err(static_error(heap, clause,
+                                        "Static error: `else` must be the last guard clause"));
+            }
+            value::list(heap, begin.as_slice())
+        } else if is_arrow || exprs.len() == 0 {
+            if is_arrow && exprs.len() != 2 {
+                return Err(static_error(heap, clause,
+                                        "Static error: improperly formed guard clause"));
+            }
+            let guard_clause = Rooted::new(heap, Value::new_primitive(
+                "guard-clause", primitives::guard_clause));
+            let receiver = if is_arrow {
+                exprs[1].clone()
+            } else {
+                Rooted::new(heap, Value::new_boolean(false))
+            };
+            let lambda = heap.lambda_symbol();
+            let empty_list = Rooted::new(heap, Value::EmptyList);
+            let otherwise = value::list(heap, &[lambda, empty_list, handler_body]);
+            enlocate_form(heap, &otherwise, &location);
+            value::list(heap, &[guard_clause, test, receiver, otherwise])
+        } else {
+            let if_symbol = heap.if_symbol();
+            let consequent = value::list(heap, begin.as_slice());
+            value::list(heap, &[if_symbol, test, consequent, handler_body])
+        };
+        enlocate_form(heap, &handler_body, &location);
+    }
+
+    let lambda = heap.lambda_symbol();
+    let empty_list = Rooted::new(heap, Value::EmptyList);
+    let mut thunk = vec!(lambda.clone(), empty_list);
+    thunk.push_all(parts.slice_from(2));
+    let thunk = value::list(heap, thunk.as_slice());
+    let params = value::list(heap, &[var]);
+    let handler = value::list(heap, &[lambda, params, handler_body]);
+    let guard = Rooted::new(heap, Value::new_primitive("guard", primitives::guard));
+    let expansion = value::list(heap, &[guard, thunk.clone(), handler.clone()]);
+
+    // Attribute the whole expansion to the original form, so that errors in it
+    // point somewhere sensible.
+    for expanded in [&thunk, &handler, &expansion].iter() {
+        enlocate_form(heap, *expanded, &location);
+    }
+
+    analyze(heap, &expansion, location)
+}
+
+/// Register the given location for the given form, if it is a pair.
+fn enlocate_form(heap: &mut Heap, form: &RootedValue, location: &Location) {
+    if let Some(pair) = form.to_pair(heap) {
+        heap.enlocate(location.clone(), pair);
+    }
+}
+
 fn analyze_invocation(heap: &mut Heap,
                       form: &RootedValue) -> MeaningResult {
     if let Some(ref cons) = form.to_pair(heap) {
         let location = heap.locate(cons);
         let proc_form = cons.car(heap);
-        let proc_meaning = try!(analyze(heap, &proc_form, location.clone()));
+        let proc_location = heap.locate_car(cons);
+        let proc_meaning = try!(analyze(heap, &proc_form, proc_location));
 
         let params_form = cons.cdr(heap);
-        let arity = try!(params_form.len().ok().ok_or(
-            "Static error: improperly formed invocation".to_string()));
+        let arity = try!(params_form.len().ok().ok_or_else(|| {
+            static_error(heap, form, "Static error: improperly formed invocation")
+        }));
         let params_meaning = try!(make_meaning_vector(
             heap, &params_form, Vec::with_capacity(arity as usize)));
 
         return Ok(Meaning::new_invocation(proc_meaning, params_meaning, location));
     }
 
-    return Err(format!("Static error: improperly formed invocation: {}", **form));
+    return Err(static_error(heap, form, "Static error: improperly formed invocation"));
 }
 
 // TESTS -----------------------------------------------------------------------
@@ -984,6 +1412,49 @@ mod tests {
             Ok(result) => assert_eq!(*result, Value::new_integer(120)),
         }
     }
+
+    #[test]
+    fn test_eval_define_record_type() {
+        let mut heap = Heap::new();
+        let result = evaluate_file(&mut heap, "./tests/test_eval_define_record_type.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "(#t #f #f 3 2 #<record point x: 3 y: 2> #<record node value: 1 next: #f> \
+                    7 #<record-type point>)");
+    }
+
+    #[test]
+    fn test_eval_guard() {
+        let mut heap = Heap::new();
+        let result = evaluate_file(&mut heap, "./tests/test_eval_guard.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "(#t (\"./tests/no-such-file\") #t #t #f \"oops\" (1 2) #t \
+                    \"Error: cannot take car of non-cons: 5\" (symbol boom) 42 (b . 23) \
+                    else 11 no-error)");
+    }
+
+    #[test]
+    fn test_eval_define_non_symbol() {
+        let mut heap = Heap::new();
+        let error = evaluate_file(&mut heap, "./tests/test_eval_define_non_symbol.scm")
+            .err()
+            .expect("Should get an error defining a non-symbol.");
+        assert_eq!(error, "./tests/test_eval_define_non_symbol.scm:1:9: \
+                           Static error: can only define symbols, found: 5");
+    }
+
+    #[test]
+    fn test_eval_bad_lambda_parameter() {
+        let mut heap = Heap::new();
+        let error = evaluate_file(&mut heap, "./tests/test_eval_bad_lambda_parameter.scm")
+            .err()
+            .expect("Should get an error with a non-symbol lambda parameter.");
+        assert_eq!(error, "./tests/test_eval_bad_lambda_parameter.scm:2:14: \
+                           Can only define symbol parameters, found 1");
+    }
 }
 
 #[cfg(test)]
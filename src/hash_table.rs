@@ -0,0 +1,278 @@
+// Copyright 2015 Nick Fitzgerald
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scheme hash tables: mutable maps from keys to values.
+//!
+//! A `HashTable` is a GC thing allocated on the heap, and strongly references
+//! all of its keys and values. Each table compares its keys with one of `eq?`,
+//! `eqv?`, `equal?` or string equality, and hashes them consistently with that
+//! comparison.
+//!
+//! Values don't have a content hash of their own (`Value`'s `Hash` is by
+//! identity, like its `PartialEq`), so entries are kept in buckets keyed by the
+//! table's own hash of the key, and keys within a bucket are compared with the
+//! table's equivalence.
+
+use std::collections::{HashMap};
+use std::default::{Default};
+use std::fmt;
+use std::hash::{self, Hash, Hasher, SipHasher};
+
+use heap::{ArenaPtr, GcThing, IterGcThing, Rooted, ToGcThing, Trace};
+use value::{Value};
+
+/// The maximum number of pairs and strings visited when hashing a key for
+/// `equal?`. Hashing stops there, so that hashing terminates on cyclic
+/// structure and stays cheap for long lists.
+static MAX_EQUAL_HASH_NODES : usize = 32;
+
+/// How a hash table compares its keys.
+#[derive(Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Equivalence {
+    Eq,
+    Eqv,
+    Equal,
+    /// Keys must be strings, and are compared by their contents.
+    String,
+}
+
+impl Equivalence {
+    /// Get the name of the Scheme procedure that compares keys in the same way.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Equivalence::Eq     => "eq?",
+            Equivalence::Eqv    => "eqv?",
+            Equivalence::Equal  => "equal?",
+            Equivalence::String => "string=?",
+        }
+    }
+
+    /// Return true if the given keys are equivalent.
+    fn equivalent(&self, a: &Value, b: &Value) -> bool {
+        match *self {
+            Equivalence::Eq     => *a == *b,
+            Equivalence::Eqv    => a.eqv(b),
+            Equivalence::Equal  => a.equal(b),
+            Equivalence::String => a.equal(b),
+        }
+    }
+
+    /// Hash the given key, such that equivalent keys have equal hashes.
+    fn hash_key(&self, key: &Value) -> u64 {
+        let mut state = SipHasher::new();
+        match *self {
+            Equivalence::Eq | Equivalence::Eqv => key.hash(&mut state),
+            Equivalence::Equal | Equivalence::String => {
+                let mut pending = vec!(*key);
+                let mut visited = 0;
+                while let Some(val) = pending.pop() {
+                    if visited == MAX_EQUAL_HASH_NODES {
+                        break;
+                    }
+                    match val {
+                        Value::Pair(cons)  => {
+                            visited += 1;
+                            0u8.hash(&mut state);
+                            pending.push(cons.cdr);
+                            pending.push(cons.car);
+                        },
+                        Value::String(str) => {
+                            visited += 1;
+                            1u8.hash(&mut state);
+                            (*str).hash(&mut state);
+                        },
+                        val                => val.hash(&mut state),
+                    }
+                }
+            },
+        }
+        state.finish()
+    }
+}
+
+/// A mutable map from keys to values.
+pub struct HashTable {
+    equivalence: Equivalence,
+    /// Each bucket holds the entries whose keys have the same hash.
+    buckets: HashMap<u64, Vec<(Value, Value)>>,
+    len: usize,
+}
+
+impl HashTable {
+    /// Create a new, empty `HashTable` that compares keys with the given
+    /// equivalence.
+    pub fn new(equivalence: Equivalence) -> HashTable {
+        HashTable {
+            equivalence: equivalence,
+            buckets: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Get the way this table compares its keys.
+    pub fn equivalence(&self) -> Equivalence {
+        self.equivalence
+    }
+
+    /// Get the number of entries in this table.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Get the value associated with the given key, if any.
+    pub fn get(&self, key: &Value) -> Option<Value> {
+        let hash = self.equivalence.hash_key(key);
+        self.buckets.get(&hash).and_then(|bucket| {
+            bucket.iter()
+                .find(|&&(ref k, _)| self.equivalence.equivalent(k, key))
+                .map(|&(_, v)| v)
+        })
+    }
+
+    /// Associate the given value with the given key, replacing any value that
+    /// was previously associated with an equivalent key.
+    pub fn insert(&mut self, key: Value, val: Value) -> Result<(), String> {
+        match (self.equivalence, key) {
+            (Equivalence::String, Value::String(_)) => { },
+            (Equivalence::String, _)                => {
+                return Err(format!("Error: string hash tables require string keys, got {}",
+                                   key));
+            },
+            _                                       => { },
+        }
+
+        let hash = self.equivalence.hash_key(&key);
+        let equivalence = self.equivalence;
+        if !self.buckets.contains_key(&hash) {
+            self.buckets.insert(hash, vec!());
+        }
+        let bucket = self.buckets.get_mut(&hash).expect("Should have a bucket");
+        for entry in bucket.iter_mut() {
+            if equivalence.equivalent(&entry.0, &key) {
+                entry.1 = val;
+                return Ok(());
+            }
+        }
+        bucket.push((key, val));
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Remove the entry for the given key, and return true if there was one.
+    pub fn remove(&mut self, key: &Value) -> bool {
+        let hash = self.equivalence.hash_key(key);
+        let equivalence = self.equivalence;
+        let (removed, now_empty) = match self.buckets.get_mut(&hash) {
+            None         => return false,
+            Some(bucket) => {
+                let position = bucket.iter()
+                    .position(|&(ref k, _)| equivalence.equivalent(k, key));
+                if let Some(i) = position {
+                    bucket.remove(i);
+                }
+                (position.is_some(), bucket.is_empty())
+            },
+        };
+        if now_empty {
+            self.buckets.remove(&hash);
+        }
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Get a copy of all of this table's entries, in no particular order.
+    pub fn entries(&self) -> Vec<(Value, Value)> {
+        self.buckets.values()
+            .flat_map(|bucket| bucket.iter().map(|entry| *entry))
+            .collect()
+    }
+}
+
+impl Default for HashTable {
+    /// Do not use this method, instead allocate hash tables on the heap with
+    /// `Heap::allocate_hash_table` and get back a `HashTablePtr`.
+    fn default() -> HashTable {
+        HashTable::new(Equivalence::Eq)
+    }
+}
+
+impl Trace for HashTable {
+    fn trace(&self) -> IterGcThing {
+        let mut results = vec!();
+        for bucket in self.buckets.values() {
+            for &(key, val) in bucket.iter() {
+                results.extend(key.to_gc_thing().into_iter());
+                results.extend(val.to_gc_thing().into_iter());
+            }
+        }
+        results.into_iter()
+    }
+}
+
+impl hash::Hash for HashTable {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.equivalence.hash(state);
+    }
+}
+
+impl fmt::Display for HashTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<hash-table {} {}>", self.equivalence.name(), self.len)
+    }
+}
+
+/// A pointer to a `HashTable` on the heap.
+pub type HashTablePtr = ArenaPtr<HashTable>;
+
+impl ToGcThing for HashTablePtr {
+    fn to_gc_thing(&self) -> Option<GcThing> {
+        Some(GcThing::from_hash_table_ptr(*self))
+    }
+}
+
+/// A rooted pointer to a `HashTable` on the heap.
+pub type RootedHashTablePtr = Rooted<HashTablePtr>;
+
+// TESTS -----------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heap::{Heap, Rooted};
+    use value::{Value};
+
+    #[test]
+    fn test_hash_table_traces_entries() {
+        let heap = &mut Heap::new();
+        let table = Value::new_hash_table(heap, HashTable::new(Equivalence::Equal));
+        {
+            let key = Value::new_string(heap, "key".to_string());
+            let one = Rooted::new(heap, Value::new_integer(1));
+            let val = Value::new_pair(heap, &one, &key);
+            table.to_hash_table(heap).expect("Should be a hash table")
+                .insert(*key, *val)
+                .ok().expect("Should insert the entry");
+        }
+
+        heap.collect_garbage();
+
+        let key = Value::new_string(heap, "key".to_string());
+        let val = table.to_hash_table(heap).expect("Should be a hash table")
+            .get(&*key)
+            .expect("Should find the entry");
+        assert_eq!(format!("{}", val), "(1 . \"key\")");
+    }
+}
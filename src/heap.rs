@@ -133,12 +133,16 @@ use std::cmp;
 use std::collections::{BitVec, HashMap};
 use std::default::{Default};
 use std::fmt;
+use std::iter::{AdditiveIterator};
 use std::ops::{Deref, DerefMut};
 use std::vec::{IntoIter};
 
 use environment::{Activation, ActivationPtr, RootedActivationPtr, Environment};
+use hash_table::{HashTable, HashTablePtr, RootedHashTablePtr};
+use port::{Port, PortPtr, RootedPortPtr};
 use primitives::{define_primitives};
 use read::{Location};
+use record::{Record, RecordPtr, RootedRecordPtr};
 use value::{Cons, ConsPtr, Procedure, ProcedurePtr, RootedConsPtr,
             RootedProcedurePtr, RootedValue, Value};
 
@@ -187,6 +191,11 @@ impl<T: Default> Arena<T> {
         self.free.len() == self.capacity()
     }
 
+    /// Get the number of objects currently allocated in this arena.
+    pub fn len(&self) -> usize {
+        self.capacity() - self.free.len()
+    }
+
     /// Allocate a new `T` instance and return a pointer to it.
     ///
     /// ## Panics
@@ -203,7 +212,16 @@ impl<T: Default> Arena<T> {
     }
 
     /// Sweep the arena and add any reclaimed objects back to the free list.
+    ///
+    /// Reclaimed objects are reset to their default, so that whatever they own
+    /// is dropped now rather than whenever their slot is next allocated. For
+    /// example, a collected file port is flushed and closed.
     pub fn sweep(&mut self) {
+        let mut was_free = BitVec::from_elem(self.capacity(), false);
+        for &n in self.free.iter() {
+            was_free.set(n, true);
+        }
+
         self.free = range(0, self.capacity())
             .filter(|&n| {
                 !self.marked.get(n)
@@ -211,6 +229,12 @@ impl<T: Default> Arena<T> {
             })
             .collect();
 
+        for &n in self.free.iter() {
+            if !was_free.get(n).expect("`was_free` has length == self.capacity()") {
+                self.pool[n] = Default::default();
+            }
+        }
+
         // Reset `marked` to all zero.
         self.marked.set_all();
         self.marked.negate();
@@ -234,6 +258,15 @@ impl<T: Default> ArenaSet<T> {
         }
     }
 
+    /// Get statistics about the objects allocated in this set of arenas.
+    pub fn stats(&self) -> ArenaSetStats {
+        ArenaSetStats {
+            arenas: self.arenas.len(),
+            live: self.arenas.iter().map(|a| a.len()).sum(),
+            capacity: self.arenas.iter().map(|a| a.capacity()).sum(),
+        }
+    }
+
     /// Sweep all of the arenas in this set.
     pub fn sweep(&mut self) {
         for arena in self.arenas.iter_mut() {
@@ -464,8 +497,54 @@ impl ToGcThing for StringPtr {
 /// A rooted pointer to a string on the heap.
 pub type RootedStringPtr = Rooted<StringPtr>;
 
+/// Statistics about the objects allocated in an `ArenaSet`.
+#[derive(Copy, Debug)]
+pub struct ArenaSetStats {
+    /// The number of arenas in the set.
+    pub arenas: usize,
+    /// The number of objects currently allocated.
+    pub live: usize,
+    /// The number of objects that could be allocated without adding arenas.
+    pub capacity: usize,
+}
+
+impl fmt::Display for ArenaSetStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} live / {} capacity in {} arenas",
+               self.live, self.capacity, self.arenas)
+    }
+}
+
+/// Statistics about the heap, e.g. for reporting in the REPL.
+#[derive(Copy, Debug)]
+pub struct HeapStats {
+    pub cons_cells: ArenaSetStats,
+    pub strings: ArenaSetStats,
+    pub activations: ArenaSetStats,
+    pub procedures: ArenaSetStats,
+    pub ports: ArenaSetStats,
+    pub hash_tables: ArenaSetStats,
+    pub records: ArenaSetStats,
+    /// The number of allocations since the heap was created.
+    pub total_allocations: u64,
+}
+
+impl fmt::Display for HeapStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(writeln!(f, "cons cells:  {}", self.cons_cells));
+        try!(writeln!(f, "strings:     {}", self.strings));
+        try!(writeln!(f, "activations: {}", self.activations));
+        try!(writeln!(f, "procedures:  {}", self.procedures));
+        try!(writeln!(f, "ports:       {}", self.ports));
+        try!(writeln!(f, "hash tables: {}", self.hash_tables));
+        try!(writeln!(f, "records:     {}", self.records));
+        write!(f, "total allocations: {}", self.total_allocations)
+    }
+}
+
 /// The scheme heap and GC runtime, containing all allocated cons cells,
-/// activations, procedures, and strings (including strings for symbols).
+/// activations, procedures, ports, hash tables, records, and strings
+/// (including strings for symbols).
 pub struct Heap {
     /// The static environment.
     pub environment: Environment,
@@ -474,14 +553,58 @@ pub struct Heap {
     strings: ArenaSet<String>,
     activations: ArenaSet<Activation>,
     procedures: ArenaSet<Procedure>,
+    ports: ArenaSet<Port>,
+    hash_tables: ArenaSet<HashTable>,
+    records: ArenaSet<Record>,
+
+    current_input_port: PortPtr,
+    current_output_port: PortPtr,
+    current_error_port: PortPtr,
 
     roots: Vec<(GcThing, usize)>,
     symbol_table: HashMap<String, StringPtr>,
     global_activation: ActivationPtr,
     allocations: usize,
     allocations_threshold: usize,
+    total_allocations: u64,
 
+    /// The source locations registered with `enlocate` and `enlocate_car`.
+    /// These don't keep their pairs alive; entries are forgotten when their
+    /// pair is collected.
     locations: HashMap<ConsPtr, Location>,
+    car_locations: HashMap<ConsPtr, Location>,
+
+    /// The program name and arguments returned by the `command-line`
+    /// primitive.
+    command_line: Vec<String>,
+
+    /// The status the program should exit with, once `exit` has been called and
+    /// the evaluation in progress has unwound.
+    exit_status: Option<i32>,
+
+    /// The record type of error objects.
+    error_object_type: RecordPtr,
+
+    /// The stack of installed exception handlers, innermost last. `None` is a
+    /// `guard`, which catches conditions by unwinding to it.
+    handlers: Vec<Option<Value>>,
+
+    /// The condition being unwound to the innermost `guard`, until the `guard`
+    /// takes it.
+    raised: Option<Value>,
+
+    /// The message of the error that is unwinding, before any locations were
+    /// added to it, until a `guard` takes it.
+    error_message: Option<String>,
+}
+
+/// An installed exception handler.
+pub enum Handler {
+    /// A procedure installed by `with-exception-handler`, which is called in
+    /// the dynamic environment of the `raise`.
+    Procedure(RootedValue),
+    /// A `guard`, which catches conditions by unwinding to it.
+    Guard,
 }
 
 /// The default capacity of cons cells per arena.
@@ -496,6 +619,16 @@ pub static DEFAULT_ACTIVATIONS_CAPACITY : usize = 1 << 10;
 /// The default capacity of procedures per arena.
 pub static DEFAULT_PROCEDURES_CAPACITY : usize = 1 << 10;
 
+/// The default capacity of ports per arena. Programs rarely have many ports
+/// open at once, so this is much smaller than the other capacities.
+pub static DEFAULT_PORTS_CAPACITY : usize = 1 << 4;
+
+/// The default capacity of hash tables per arena.
+pub static DEFAULT_HASH_TABLES_CAPACITY : usize = 1 << 6;
+
+/// The default capacity of records per arena.
+pub static DEFAULT_RECORDS_CAPACITY : usize = 1 << 10;
+
 /// ## `Heap` Constructors
 impl Heap {
     /// Create a new `Heap` with the default capacity.
@@ -503,19 +636,39 @@ impl Heap {
         Heap::with_arenas(ArenaSet::new(DEFAULT_CONS_CAPACITY),
                           ArenaSet::new(DEFAULT_STRINGS_CAPACITY),
                           ArenaSet::new(DEFAULT_ACTIVATIONS_CAPACITY),
-                          ArenaSet::new(DEFAULT_PROCEDURES_CAPACITY))
+                          ArenaSet::new(DEFAULT_PROCEDURES_CAPACITY),
+                          ArenaSet::new(DEFAULT_PORTS_CAPACITY),
+                          ArenaSet::new(DEFAULT_HASH_TABLES_CAPACITY),
+                          ArenaSet::new(DEFAULT_RECORDS_CAPACITY))
     }
 
-    /// Create a new `Heap` using the given arenas for allocating cons cells and
-    /// strings within.
+    /// Create a new `Heap` using the given arenas for allocating cons cells,
+    /// strings, activations, procedures, ports, hash tables and records
+    /// within.
     pub fn with_arenas(cons_cells: ArenaSet<Cons>,
                        strings: ArenaSet<String>,
                        mut acts: ArenaSet<Activation>,
-                       procs: ArenaSet<Procedure>) -> Heap {
+                       procs: ArenaSet<Procedure>,
+                       mut ports: ArenaSet<Port>,
+                       hash_tables: ArenaSet<HashTable>,
+                       mut records: ArenaSet<Record>) -> Heap {
         let mut global_act = acts.allocate();
         let mut env = Environment::new();
         define_primitives(&mut env, &mut global_act);
 
+        let mut stdin = ports.allocate();
+        *stdin = Port::stdin();
+        let mut stdout = ports.allocate();
+        *stdout = Port::stdout();
+        let mut stderr = ports.allocate();
+        *stderr = Port::stderr();
+
+        let mut error_object_type = records.allocate();
+        *error_object_type = Record::new_type("<error-object>".to_string(),
+                                              vec!("kind".to_string(),
+                                                   "message".to_string(),
+                                                   "irritants".to_string()));
+
         let mut h = Heap {
             environment: env,
 
@@ -523,14 +676,31 @@ impl Heap {
             strings: strings,
             activations: acts,
             procedures: procs,
+            ports: ports,
+            hash_tables: hash_tables,
+            records: records,
+
+            current_input_port: stdin,
+            current_output_port: stdout,
+            current_error_port: stderr,
 
             global_activation: global_act,
             roots: vec!(),
             symbol_table: HashMap::new(),
             allocations: 0,
             allocations_threshold: 0,
+            total_allocations: 0,
 
-            locations: HashMap::new()
+            locations: HashMap::new(),
+            car_locations: HashMap::new(),
+
+            command_line: vec!(),
+            exit_status: None,
+
+            error_object_type: error_object_type,
+            handlers: vec!(),
+            raised: None,
+            error_message: None,
         };
 
         h.reset_gc_pressure();
@@ -584,6 +754,30 @@ impl Heap {
         let p = self.procedures.allocate();
         Rooted::new(self, p)
     }
+
+    /// Allocate a new `Port` and return a pointer to it.
+    pub fn allocate_port(&mut self, port: Port) -> RootedPortPtr {
+        self.on_allocation();
+        let mut p = self.ports.allocate();
+        *p = port;
+        Rooted::new(self, p)
+    }
+
+    /// Allocate a new `HashTable` and return a pointer to it.
+    pub fn allocate_hash_table(&mut self, table: HashTable) -> RootedHashTablePtr {
+        self.on_allocation();
+        let mut t = self.hash_tables.allocate();
+        *t = table;
+        Rooted::new(self, t)
+    }
+
+    /// Allocate a new `Record` and return a pointer to it.
+    pub fn allocate_record(&mut self, record: Record) -> RootedRecordPtr {
+        self.on_allocation();
+        let mut r = self.records.allocate();
+        *r = record;
+        Rooted::new(self, r)
+    }
 }
 
 /// ## `Heap` Methods for Garbage Collection
@@ -612,12 +806,107 @@ impl Heap {
             pending_trace.append(&mut newly_pending_trace);
         }
 
-        // Second, sweep each `ArenaSet`.
+        // Second, forget the source locations of pairs that are about to be
+        // swept. The location maps are weak, so that data read at runtime
+        // isn't kept alive just because the reader recorded where it was.
+
+        self.forget_unmarked_locations();
+
+        // Third, sweep each `ArenaSet`.
 
         self.strings.sweep();
         self.activations.sweep();
         self.cons_cells.sweep();
         self.procedures.sweep();
+        self.ports.sweep();
+        self.hash_tables.sweep();
+        self.records.sweep();
+    }
+
+    /// Get statistics about the objects allocated in the heap.
+    pub fn stats(&self) -> HeapStats {
+        HeapStats {
+            cons_cells: self.cons_cells.stats(),
+            strings: self.strings.stats(),
+            activations: self.activations.stats(),
+            procedures: self.procedures.stats(),
+            ports: self.ports.stats(),
+            hash_tables: self.hash_tables.stats(),
+            records: self.records.stats(),
+            total_allocations: self.total_allocations,
+        }
+    }
+
+    /// Get the program name and arguments of the running script.
+    pub fn command_line(&self) -> &[String] {
+        self.command_line.as_slice()
+    }
+
+    /// Set the program name and arguments returned by the `command-line`
+    /// primitive.
+    pub fn set_command_line(&mut self, command_line: Vec<String>) {
+        self.command_line = command_line;
+    }
+
+    /// Get the status the program should exit with, or `None` if `exit` has
+    /// not been called.
+    pub fn exit_status(&self) -> Option<i32> {
+        self.exit_status
+    }
+
+    /// Request that the program exit with the given status. The caller should
+    /// then return an error, so that the evaluation in progress unwinds.
+    pub fn request_exit(&mut self, status: i32) {
+        self.exit_status = Some(status);
+    }
+
+    /// Get the record type of the error objects passed to exception handlers.
+    pub fn error_object_type(&mut self) -> RootedRecordPtr {
+        let record_type = self.error_object_type;
+        Rooted::new(self, record_type)
+    }
+
+    /// Install the given exception handler.
+    pub fn push_handler(&mut self, handler: Handler) {
+        self.handlers.push(match handler {
+            Handler::Procedure(procedure) => Some(*procedure),
+            Handler::Guard                => None,
+        });
+    }
+
+    /// Uninstall the innermost exception handler and return it, or `None` if
+    /// there are no handlers installed.
+    pub fn pop_handler(&mut self) -> Option<Handler> {
+        match self.handlers.pop() {
+            Some(Some(procedure)) => Some(Handler::Procedure(Rooted::new(self, procedure))),
+            Some(None)            => Some(Handler::Guard),
+            None                  => None,
+        }
+    }
+
+    /// Unwind the given condition to the innermost `guard`. The caller should
+    /// then return an error, so that the evaluation in progress unwinds.
+    pub fn raise(&mut self, condition: &RootedValue) {
+        self.raised = Some(**condition);
+    }
+
+    /// Take the condition being unwound to the innermost `guard`, if any.
+    pub fn take_raised(&mut self) -> Option<RootedValue> {
+        self.raised.take().map(|condition| Rooted::new(self, condition))
+    }
+
+    /// Record the message of the error that is unwinding, unless one is
+    /// already recorded. This is called with the message before any locations
+    /// have been added to it.
+    pub fn note_error_message(&mut self, message: &str) {
+        if self.error_message.is_none() {
+            self.error_message = Some(message.to_string());
+        }
+    }
+
+    /// Take the message of the error that is unwinding, if any.
+    pub fn take_error_message(&mut self) -> Option<String> {
+        self.error_message.take()
     }
 
     /// Explicitly add the given GC thing as a root.
@@ -669,21 +958,56 @@ impl Heap {
             .collect();
 
         roots.push(GcThing::from_activation_ptr(self.global_activation));
+        roots.push(GcThing::from_port_ptr(self.current_input_port));
+        roots.push(GcThing::from_port_ptr(self.current_output_port));
+        roots.push(GcThing::from_port_ptr(self.current_error_port));
+        roots.push(GcThing::from_record_ptr(self.error_object_type));
+
+        for handler in self.handlers.iter() {
+            if let Some(procedure) = handler.and_then(|p| p.to_gc_thing()) {
+                roots.push(procedure);
+            }
+        }
+
+        if let Some(condition) = self.raised.and_then(|c| c.to_gc_thing()) {
+            roots.push(condition);
+        }
 
         for pair in self.roots.iter() {
             let (ref root, _) = *pair;
             roots.push(*root);
         }
 
-        for cons in self.locations.keys() {
-            roots.push(GcThing::from_cons_ptr(*cons));
+        roots
+    }
+
+    /// Remove the registered source locations of every pair that was not
+    /// marked during the current collection.
+    fn forget_unmarked_locations(&mut self) {
+        fn is_unmarked(cons: &&ConsPtr) -> bool {
+            !GcThing::from_cons_ptr(**cons).is_marked()
         }
 
-        roots
+        let dead : Vec<ConsPtr> = self.locations.keys()
+            .filter(is_unmarked)
+            .map(|cons| *cons)
+            .collect();
+        for cons in dead.iter() {
+            self.locations.remove(cons);
+        }
+
+        let dead : Vec<ConsPtr> = self.car_locations.keys()
+            .filter(is_unmarked)
+            .map(|cons| *cons)
+            .collect();
+        for cons in dead.iter() {
+            self.car_locations.remove(cons);
+        }
     }
 
     /// A method that should be called on every allocation.
     fn on_allocation(&mut self)  {
+        self.total_allocations += 1;
         self.increase_gc_pressure();
     }
 
@@ -702,7 +1026,39 @@ impl Heap {
             ((self.cons_cells.capacity / 2) * self.cons_cells.arenas.len())
             + ((self.strings.capacity / 2) * self.strings.arenas.len())
             + ((self.activations.capacity / 2) * self.activations.arenas.len())
-            + ((self.procedures.capacity / 2) * self.procedures.arenas.len());
+            + ((self.procedures.capacity / 2) * self.procedures.arenas.len())
+            + ((self.ports.capacity / 2) * self.ports.arenas.len())
+            + ((self.hash_tables.capacity / 2) * self.hash_tables.arenas.len())
+            + ((self.records.capacity / 2) * self.records.arenas.len());
+    }
+}
+
+/// ## `Heap` Methods for Ports
+impl Heap {
+    /// Get the port that input procedures read from by default.
+    pub fn current_input_port(&mut self) -> RootedPortPtr {
+        let port = self.current_input_port;
+        Rooted::new(self, port)
+    }
+
+    /// Get the port that output procedures write to by default.
+    pub fn current_output_port(&mut self) -> RootedPortPtr {
+        let port = self.current_output_port;
+        Rooted::new(self, port)
+    }
+
+    /// Make the given port the current output port, and return the previous
+    /// current output port.
+    pub fn set_current_output_port(&mut self, port: &RootedPortPtr) -> RootedPortPtr {
+        let previous = self.current_output_port;
+        self.current_output_port = **port;
+        Rooted::new(self, previous)
+    }
+
+    /// Get the port that errors are written to.
+    pub fn current_error_port(&mut self) -> RootedPortPtr {
+        let port = self.current_error_port;
+        Rooted::new(self, port)
     }
 }
 
@@ -733,13 +1089,29 @@ impl Heap {
         self.locations.insert(*cons, loc);
     }
 
+    /// Register the datum in the car of the given pair as having originated
+    /// from the given location. Unlike pairs, atoms such as symbols and
+    /// integers have no identity of their own, so their locations are
+    /// registered by their position in the containing pair.
+    pub fn enlocate_car(&mut self, loc: Location, cons: RootedConsPtr) {
+        self.car_locations.insert(*cons, loc);
+    }
+
     /// Get the registered source location of the given pair. If the pair was
-    /// not created by the reader, then None is returned.
+    /// not created by the reader, then `Location::unknown()` is returned.
     pub fn locate(&self, cons: &RootedConsPtr) -> Location {
         self.locations.get(&**cons)
             .map(|loc| loc.clone())
             .unwrap_or_else(Location::unknown)
     }
+
+    /// Get the registered source location of the datum in the car of the given
+    /// pair. If there is none, fall back to the location of the pair itself.
+    pub fn locate_car(&self, cons: &RootedConsPtr) -> Location {
+        self.car_locations.get(&**cons)
+            .map(|loc| loc.clone())
+            .unwrap_or_else(|| self.locate(cons))
+    }
 }
 
 /// ## `Heap` Methods for Symbols
@@ -760,6 +1132,11 @@ impl Heap {
         return Value::new_symbol(self, symbol);
     }
 
+    /// Get the names of all of the interned symbols.
+    pub fn symbol_names<'a>(&'a self) -> Vec<&'a String> {
+        self.symbol_table.keys().collect()
+    }
+
     pub fn quote_symbol(&mut self) -> RootedValue {
         self.get_or_create_symbol("quote".to_string())
     }
@@ -788,10 +1165,12 @@ impl Heap {
         self.get_or_create_symbol("lambda".to_string())
     }
 
-    pub fn eof_symbol(&mut self) -> RootedValue {
-        // Per R4RS, the EOF object must be something that is impossible to
-        // read. We fulfill that contract by having spaces in a symbol.
-        self.get_or_create_symbol("< END OF FILE >".to_string())
+    pub fn define_record_type_symbol(&mut self) -> RootedValue {
+        self.get_or_create_symbol("define-record-type".to_string())
+    }
+
+    pub fn guard_symbol(&mut self) -> RootedValue {
+        self.get_or_create_symbol("guard".to_string())
     }
 }
 
@@ -834,6 +1213,9 @@ pub enum GcThing {
     String(StringPtr),
     Activation(ActivationPtr),
     Procedure(ProcedurePtr),
+    Port(PortPtr),
+    HashTable(HashTablePtr),
+    Record(RecordPtr),
 }
 
 /// ## `GcThing` Constructors
@@ -857,6 +1239,21 @@ impl GcThing {
     pub fn from_activation_ptr(act: ActivationPtr) -> GcThing {
         GcThing::Activation(act)
     }
+
+    /// Create a `GcThing` from a `PortPtr`.
+    pub fn from_port_ptr(port: PortPtr) -> GcThing {
+        GcThing::Port(port)
+    }
+
+    /// Create a `GcThing` from a `HashTablePtr`.
+    pub fn from_hash_table_ptr(table: HashTablePtr) -> GcThing {
+        GcThing::HashTable(table)
+    }
+
+    /// Create a `GcThing` from a `RecordPtr`.
+    pub fn from_record_ptr(record: RecordPtr) -> GcThing {
+        GcThing::Record(record)
+    }
 }
 
 impl GcThing {
@@ -868,6 +1265,9 @@ impl GcThing {
             GcThing::String(ref p) => p.mark(),
             GcThing::Activation(ref p) => p.mark(),
             GcThing::Procedure(ref p) => p.mark(),
+            GcThing::Port(ref p) => p.mark(),
+            GcThing::HashTable(ref p) => p.mark(),
+            GcThing::Record(ref p) => p.mark(),
         }
     }
 
@@ -879,6 +1279,9 @@ impl GcThing {
             GcThing::String(ref p) => p.is_marked(),
             GcThing::Activation(ref p) => p.is_marked(),
             GcThing::Procedure(ref p) => p.is_marked(),
+            GcThing::Port(ref p) => p.is_marked(),
+            GcThing::HashTable(ref p) => p.is_marked(),
+            GcThing::Record(ref p) => p.is_marked(),
         }
     }
 }
@@ -889,12 +1292,56 @@ impl Trace for GcThing {
             GcThing::Cons(cons)      => cons.trace(),
             GcThing::Activation(act) => act.trace(),
             GcThing::Procedure(p)    => p.trace(),
+            GcThing::Port(p)         => p.trace(),
+            GcThing::HashTable(p)    => p.trace(),
+            GcThing::Record(p)       => p.trace(),
             // Strings don't hold any strong references to other `GcThing`s.
             GcThing::String(_)       => vec!().into_iter(),
         }
     }
 }
 
+#[test]
+fn test_heap_collect_closes_ports() {
+    use std::old_io::{File};
+    use std::old_io::fs;
+
+    let path = "./tests/test_heap_collect_closes_ports.tmp";
+    let heap = &mut Heap::new();
+    {
+        let mut port = heap.allocate_port(Port::open_output_file(path)
+                                          .ok().expect("Should open the file"));
+        port.write_str("unflushed").ok().expect("Should write to the port");
+    }
+
+    heap.collect_garbage();
+
+    let contents = File::open(&Path::new(path)).read_to_string()
+        .ok().expect("Should read the file");
+    fs::unlink(&Path::new(path)).ok().expect("Should delete the file");
+    assert_eq!(contents, "unflushed");
+}
+
+#[test]
+fn test_heap_collect_forgets_locations() {
+    use read::{read_from_str};
+
+    let heap = &mut Heap::new();
+    {
+        let (_, result) = read_from_str("(a (b c) d)", heap, "test").next()
+            .expect("Should read a datum");
+        let val = result.ok().expect("Should not get a read error");
+        let pair = val.to_pair(heap).expect("Should be a pair");
+        assert!(heap.locations.contains_key(&*pair));
+        heap.collect_garbage();
+        assert!(heap.locations.contains_key(&*pair));
+    }
+
+    heap.collect_garbage();
+    assert!(heap.locations.is_empty());
+    assert!(heap.car_locations.is_empty());
+}
+
 #[test]
 fn test_heap_allocate_tons() {
     use eval::evaluate_file;
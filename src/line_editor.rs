@@ -0,0 +1,661 @@
+// Copyright 2015 Nick Fitzgerald
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A small line editor for the REPL.
+//!
+//! It supports emacs style key bindings and the arrow keys for moving around
+//! and editing the line, history which persists between sessions, reverse
+//! incremental search through the history with `C-r`, tab completion, and
+//! highlighting the parenthesis that matches the one just before the cursor.
+//!
+//! The terminal is put in raw mode only while a line is being read. When stdin
+//! is not a terminal, e.g. because input is piped in, lines are read without
+//! any editing.
+
+use std::cmp;
+use std::old_io;
+use std::old_io::{Append, File, IoErrorKind, IoResult, Write};
+
+/// The maximum number of lines of history to keep.
+static MAX_HISTORY : usize = 1000;
+
+/// The result of reading a line of input.
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    /// A line of input, including the trailing newline.
+    Line(String),
+    /// The user pressed `C-c` to abandon the current input.
+    Interrupted,
+    /// The end of input was reached, or the user pressed `C-d` on an empty
+    /// line.
+    Eof,
+}
+
+/// A function which, given the prefix of a word, returns the possible
+/// completions of the whole word.
+pub type Completer<'a> = &'a Fn(&str) -> Vec<String>;
+
+/// Reads lines of input, with editing when stdin is a terminal.
+pub struct LineEditor {
+    history: Vec<String>,
+    /// The file that history is loaded from and saved to, if any.
+    history_path: Option<Path>,
+}
+
+impl LineEditor {
+    /// Create a new `LineEditor`, loading any history from the given file.
+    pub fn new(history_path: Option<Path>) -> LineEditor {
+        let history = history_path.as_ref()
+            .and_then(|path| File::open(path).read_to_string().ok())
+            .map(|contents| contents.lines().map(|l| l.to_string()).collect())
+            .unwrap_or_else(|| vec!());
+
+        let mut editor = LineEditor {
+            history: history,
+            history_path: history_path,
+        };
+        editor.trim_history();
+        editor
+    }
+
+    /// Get the lines of history, oldest first.
+    pub fn history(&self) -> &[String] {
+        self.history.as_slice()
+    }
+
+    /// Add the given line to the history, and save it to the history file.
+    /// Blank lines and repeats of the previous line are ignored.
+    pub fn add_history(&mut self, line: &str) {
+        let line = line.trim_right_matches('\n');
+        if line.trim().is_empty() || self.history.last().map(|l| l.as_slice()) == Some(line) {
+            return;
+        }
+
+        self.history.push(line.to_string());
+        self.trim_history();
+
+        if let Some(ref path) = self.history_path {
+            // Failing to save history is not worth interrupting the user for.
+            let _ = File::open_mode(path, Append, Write)
+                .and_then(|mut file| file.write_line(line));
+        }
+    }
+
+    /// Read a line of input after printing the given prompt. When stdin is a
+    /// terminal, the line may be edited, and `complete` is used for tab
+    /// completion.
+    pub fn read_line(&mut self, prompt: &str, complete: Completer) -> Input {
+        if !raw::is_tty(0) || !raw::is_tty(1) {
+            return read_plain_line(prompt);
+        }
+
+        match raw::RawMode::enable() {
+            Some(_raw_mode) => self.read_edited_line(prompt, complete),
+            None            => read_plain_line(prompt),
+        }
+    }
+
+    /// Read and edit a line of input, assuming the terminal is in raw mode.
+    fn read_edited_line(&mut self, prompt: &str, complete: Completer) -> Input {
+        let mut input = old_io::stdio::stdin_raw();
+        let mut editor = Editor {
+            prompt: prompt,
+            line: vec!(),
+            cursor: 0,
+        };
+
+        // The position in history being edited, and the line that was being
+        // edited before moving into history.
+        let mut history_index = self.history.len();
+        let mut saved_line : Vec<char> = vec!();
+
+        editor.refresh();
+        loop {
+            let c = match read_char(&mut input) {
+                Some(c) => c,
+                None    => return Input::Eof,
+            };
+
+            match c {
+                '\r' | '\n' => return editor.finish(),
+                '\x03'      => {
+                    // C-c
+                    write_out("^C\r\n");
+                    return Input::Interrupted;
+                },
+                '\x04'      => {
+                    // C-d
+                    if editor.line.is_empty() {
+                        write_out("\r\n");
+                        return Input::Eof;
+                    }
+                    editor.delete();
+                },
+                '\x01'      => editor.cursor = 0,
+                '\x05'      => editor.cursor = editor.line.len(),
+                '\x02'      => editor.left(),
+                '\x06'      => editor.right(),
+                '\x7f' | '\x08' => editor.backspace(),
+                '\x0b'      => editor.line.truncate(editor.cursor),
+                '\x15'      => {
+                    // C-u
+                    editor.line = editor.line.slice_from(editor.cursor).to_vec();
+                    editor.cursor = 0;
+                },
+                '\x0c'      => write_out("\x1b[H\x1b[2J"),
+                '\t'        => editor.complete(complete),
+                '\x12'      => {
+                    // C-r
+                    match self.reverse_search(&mut input, &mut editor) {
+                        Some(input) => return input,
+                        None        => { },
+                    }
+                },
+                '\x10' | '\x0e' => {
+                    // C-p and C-n
+                    self.move_in_history(c == '\x10', &mut history_index,
+                                         &mut saved_line, &mut editor);
+                },
+                '\x1b'      => {
+                    match read_escape_sequence(&mut input).as_slice() {
+                        "[A" | "OA"  => self.move_in_history(true, &mut history_index,
+                                                             &mut saved_line, &mut editor),
+                        "[B" | "OB"  => self.move_in_history(false, &mut history_index,
+                                                             &mut saved_line, &mut editor),
+                        "[C" | "OC"  => editor.right(),
+                        "[D" | "OD"  => editor.left(),
+                        "[H" | "OH" | "[1~" => editor.cursor = 0,
+                        "[F" | "OF" | "[4~" => editor.cursor = editor.line.len(),
+                        "[3~"        => editor.delete(),
+                        _            => { },
+                    }
+                },
+                c if c.is_control() => { },
+                c           => editor.insert(c),
+            }
+
+            editor.refresh();
+        }
+    }
+
+    /// Move backwards (`older` is true) or forwards in history, replacing the
+    /// line being edited.
+    fn move_in_history(&self,
+                       older: bool,
+                       index: &mut usize,
+                       saved_line: &mut Vec<char>,
+                       editor: &mut Editor) {
+        if older {
+            if *index == 0 {
+                return;
+            }
+            if *index == self.history.len() {
+                *saved_line = editor.line.clone();
+            }
+            *index -= 1;
+        } else {
+            if *index >= self.history.len() {
+                return;
+            }
+            *index += 1;
+        }
+
+        editor.line = if *index == self.history.len() {
+            saved_line.clone()
+        } else {
+            self.history[*index].chars().collect()
+        };
+        editor.cursor = editor.line.len();
+    }
+
+    /// Incrementally search backwards through history. Returns `Some` if the
+    /// search finished reading the line, or `None` if editing should continue
+    /// with the line that was found.
+    fn reverse_search<R: Reader>(&self, input: &mut R, editor: &mut Editor) -> Option<Input> {
+        let mut query = String::new();
+        let mut index = self.history.len();
+        let mut found : Option<usize> = None;
+
+        loop {
+            let matched = found.map(|i| self.history[i].as_slice()).unwrap_or("");
+            write_out(format!("\r(reverse-i-search)`{}': {}\x1b[K", query, matched).as_slice());
+
+            let c = match read_char(input) {
+                Some(c) => c,
+                None    => return Some(Input::Eof),
+            };
+
+            match c {
+                '\x12'          => {
+                    // Search for an older match of the same query.
+                    index = found.unwrap_or(index);
+                },
+                '\x7f' | '\x08' => {
+                    query.pop();
+                    index = self.history.len();
+                },
+                '\x03' | '\x07' => {
+                    // C-c and C-g cancel the search.
+                    return None;
+                },
+                c if !c.is_control() => {
+                    query.push(c);
+                    index = found.map(|i| i + 1).unwrap_or(self.history.len());
+                },
+                c               => {
+                    if let Some(i) = found {
+                        editor.line = self.history[i].chars().collect();
+                        editor.cursor = editor.line.len();
+                    }
+                    if c == '\r' || c == '\n' {
+                        return Some(editor.finish());
+                    }
+                    return None;
+                },
+            }
+
+            found = self.history.slice_to(index).iter()
+                .rposition(|line| line.contains(query.as_slice()))
+                .or(found);
+        }
+    }
+
+    /// Drop the oldest history beyond `MAX_HISTORY` lines.
+    fn trim_history(&mut self) {
+        if self.history.len() > MAX_HISTORY {
+            let excess = self.history.len() - MAX_HISTORY;
+            self.history = self.history.slice_from(excess).to_vec();
+        }
+    }
+}
+
+/// The state of a line that is being edited.
+struct Editor<'a> {
+    prompt: &'a str,
+    line: Vec<char>,
+    /// The index in `line` that the cursor is before.
+    cursor: usize,
+}
+
+impl<'a> Editor<'a> {
+    fn insert(&mut self, c: char) {
+        self.line.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.line.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.line.len() {
+            self.line.remove(self.cursor);
+        }
+    }
+
+    fn left(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+        }
+    }
+
+    fn right(&mut self) {
+        self.cursor = cmp::min(self.cursor + 1, self.line.len());
+    }
+
+    /// Finish editing, and return the line.
+    fn finish(&mut self) -> Input {
+        self.cursor = self.line.len();
+        self.refresh();
+        write_out("\r\n");
+
+        let mut line : String = self.line.iter().map(|c| *c).collect();
+        line.push('\n');
+        Input::Line(line)
+    }
+
+    /// Complete the word before the cursor. If there is a single completion,
+    /// insert it. If there are many, insert their common prefix, or list them
+    /// all if there is no common prefix to insert.
+    fn complete(&mut self, complete: Completer) {
+        let start = self.line.slice_to(self.cursor).iter()
+            .rposition(|c| !is_word_char(c))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let prefix : String = self.line.slice(start, self.cursor).iter().map(|c| *c).collect();
+
+        let completions = complete(prefix.as_slice());
+        if completions.is_empty() {
+            write_out("\x07");
+            return;
+        }
+
+        let common = common_prefix(completions.as_slice());
+        if common.chars().count() > prefix.chars().count() {
+            for c in common.chars().skip(prefix.chars().count()) {
+                self.insert(c);
+            }
+            if completions.len() == 1 {
+                self.insert(' ');
+            }
+            return;
+        }
+
+        write_out("\r\n");
+        write_out(completions.connect("  ").as_slice());
+        write_out("\r\n");
+    }
+
+    /// Redraw the prompt and line, and put the cursor in its place.
+    fn refresh(&self) {
+        let highlight = matching_paren(self.line.as_slice(), self.cursor);
+
+        let mut out = String::from_str("\r");
+        out.push_str(self.prompt);
+        for (i, c) in self.line.iter().enumerate() {
+            if Some(i) == highlight {
+                out.push_str("\x1b[7m");
+                out.push(*c);
+                out.push_str("\x1b[0m");
+            } else {
+                out.push(*c);
+            }
+        }
+        out.push_str("\x1b[K\r");
+
+        let column = self.prompt.chars().count() + self.cursor;
+        if column > 0 {
+            out.push_str(format!("\x1b[{}C", column).as_slice());
+        }
+        write_out(out.as_slice());
+    }
+}
+
+/// Return true if the character can be part of a word being completed, false
+/// otherwise.
+fn is_word_char(c: &char) -> bool {
+    !c.is_whitespace() && *c != '(' && *c != ')' && *c != '\'' && *c != '"'
+}
+
+/// Get the longest common prefix of the given strings.
+fn common_prefix(strs: &[String]) -> String {
+    let first = match strs.first() {
+        Some(first) => first,
+        None        => return String::new(),
+    };
+
+    let mut len = first.chars().count();
+    for s in strs.iter().skip(1) {
+        len = cmp::min(len, first.chars()
+                       .zip(s.chars())
+                       .take_while(|&(a, b)| a == b)
+                       .count());
+    }
+    first.chars().take(len).collect()
+}
+
+/// If the character just before the cursor is a ')', get the index of the '('
+/// that matches it.
+fn matching_paren(line: &[char], cursor: usize) -> Option<usize> {
+    if cursor == 0 || line[cursor - 1] != ')' {
+        return None;
+    }
+
+    let mut depth = 0;
+    for i in range(0, cursor).rev() {
+        match line[i] {
+            ')' => depth += 1,
+            '(' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            },
+            _   => { },
+        }
+    }
+    None
+}
+
+/// Read a line without any editing, for when stdin is not a terminal.
+fn read_plain_line(prompt: &str) -> Input {
+    print!("{}", prompt);
+    old_io::stdio::flush();
+
+    match old_io::stdio::stdin().read_line() {
+        Ok(line)                                       => Input::Line(line),
+        Err(ref e) if e.kind == IoErrorKind::EndOfFile => Input::Eof,
+        Err(e)                                         => panic!("IO ERROR! {}", e),
+    }
+}
+
+/// Read a single UTF-8 encoded character, or `None` at EOF.
+fn read_char<R: Reader>(input: &mut R) -> Option<char> {
+    let first = match input.read_byte() {
+        Ok(b)  => b,
+        Err(_) => return None,
+    };
+
+    let len = match first {
+        b if b < 0x80 => 1,
+        b if b < 0xe0 => 2,
+        b if b < 0xf0 => 3,
+        _             => 4,
+    };
+
+    let mut bytes = vec!(first);
+    for _ in range(1, len) {
+        match input.read_byte() {
+            Ok(b)  => bytes.push(b),
+            Err(_) => return None,
+        }
+    }
+
+    String::from_utf8(bytes).ok().and_then(|s| s.chars().next())
+}
+
+/// Read the rest of an escape sequence, after the leading escape character,
+/// e.g. "[A" for the up arrow key.
+fn read_escape_sequence<R: Reader>(input: &mut R) -> String {
+    let mut seq = String::new();
+    loop {
+        match read_char(input) {
+            None    => return seq,
+            Some(c) => {
+                seq.push(c);
+                // Escape sequences end in a letter or '~', after the leading
+                // '[' or 'O'.
+                if seq.len() > 1 && (c.is_alphabetic() || c == '~') {
+                    return seq;
+                }
+                if seq.len() == 1 && c != '[' && c != 'O' {
+                    return seq;
+                }
+            },
+        }
+    }
+}
+
+/// Get the width of the terminal attached to stdout, in columns, or `None` if
+/// stdout is not a terminal.
+pub fn terminal_width() -> Option<usize> {
+    if !raw::is_tty(1) {
+        return None;
+    }
+    raw::terminal_width(1)
+}
+
+/// Write the given string to stdout, unbuffered.
+fn write_out(s: &str) {
+    let result : IoResult<()> = old_io::stdio::stdout_raw().write_str(s);
+    result.ok().expect("IO ERROR!");
+}
+
+/// Raw terminal mode, on platforms where we know how to enable it.
+#[cfg(target_os = "linux")]
+mod raw {
+    use libc::{c_int, c_uchar, c_uint, c_ulong, c_ushort};
+
+    #[repr(C)]
+    #[derive(Copy)]
+    struct Termios {
+        c_iflag: c_uint,
+        c_oflag: c_uint,
+        c_cflag: c_uint,
+        c_lflag: c_uint,
+        c_line: c_uchar,
+        c_cc: [c_uchar; 32],
+        c_ispeed: c_uint,
+        c_ospeed: c_uint,
+    }
+
+    static BRKINT : c_uint = 0o2;
+    static ICRNL : c_uint = 0o400;
+    static INPCK : c_uint = 0o20;
+    static ISTRIP : c_uint = 0o40;
+    static IXON : c_uint = 0o2000;
+    static OPOST : c_uint = 0o1;
+    static CS8 : c_uint = 0o60;
+    static ECHO : c_uint = 0o10;
+    static ICANON : c_uint = 0o2;
+    static IEXTEN : c_uint = 0o100000;
+    static ISIG : c_uint = 0o1;
+    static VTIME : usize = 5;
+    static VMIN : usize = 6;
+    static TCSAFLUSH : c_int = 2;
+
+    #[repr(C)]
+    #[derive(Copy)]
+    struct Winsize {
+        ws_row: c_ushort,
+        ws_col: c_ushort,
+        ws_xpixel: c_ushort,
+        ws_ypixel: c_ushort,
+    }
+
+    static TIOCGWINSZ : c_ulong = 0x5413;
+
+    extern {
+        fn isatty(fd: c_int) -> c_int;
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+        fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+        fn tcsetattr(fd: c_int, action: c_int, termios: *const Termios) -> c_int;
+    }
+
+    /// Return true if the given file descriptor is a terminal.
+    pub fn is_tty(fd: c_int) -> bool {
+        unsafe { isatty(fd) == 1 }
+    }
+
+    /// Get the width in columns of the terminal that the given file descriptor
+    /// is attached to.
+    pub fn terminal_width(fd: c_int) -> Option<usize> {
+        unsafe {
+            let mut size : Winsize = ::std::mem::zeroed();
+            if ioctl(fd, TIOCGWINSZ, &mut size) != 0 || size.ws_col == 0 {
+                return None;
+            }
+            Some(size.ws_col as usize)
+        }
+    }
+
+    /// The terminal is in raw mode for as long as a `RawMode` is alive.
+    pub struct RawMode {
+        original: Termios,
+    }
+
+    impl RawMode {
+        /// Put the terminal attached to stdin into raw mode, or return `None`
+        /// if that fails.
+        pub fn enable() -> Option<RawMode> {
+            unsafe {
+                let mut original : Termios = ::std::mem::zeroed();
+                if tcgetattr(0, &mut original) != 0 {
+                    return None;
+                }
+
+                let mut raw = original;
+                raw.c_iflag &= !(BRKINT | ICRNL | INPCK | ISTRIP | IXON);
+                raw.c_oflag &= !OPOST;
+                raw.c_cflag |= CS8;
+                raw.c_lflag &= !(ECHO | ICANON | IEXTEN | ISIG);
+                raw.c_cc[VMIN] = 1;
+                raw.c_cc[VTIME] = 0;
+                if tcsetattr(0, TCSAFLUSH, &raw) != 0 {
+                    return None;
+                }
+
+                Some(RawMode { original: original })
+            }
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe {
+                tcsetattr(0, TCSAFLUSH, &self.original);
+            }
+        }
+    }
+}
+
+/// Raw terminal mode is not supported on this platform, so we always read
+/// lines without editing.
+#[cfg(not(target_os = "linux"))]
+mod raw {
+    use libc::{c_int};
+
+    pub fn is_tty(_: c_int) -> bool {
+        false
+    }
+
+    pub fn terminal_width(_: c_int) -> Option<usize> {
+        None
+    }
+
+    pub struct RawMode;
+
+    impl RawMode {
+        pub fn enable() -> Option<RawMode> {
+            None
+        }
+    }
+}
+
+// TESTS -----------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{common_prefix, matching_paren};
+
+    #[test]
+    fn test_line_editor_common_prefix() {
+        let strs = vec!("char-upcase".to_string(),
+                        "char-upper-case?".to_string(),
+                        "char-up".to_string());
+        assert_eq!(common_prefix(strs.as_slice()), "char-up".to_string());
+        assert_eq!(common_prefix(&[]), "".to_string());
+    }
+
+    #[test]
+    fn test_line_editor_matching_paren() {
+        let line : Vec<char> = "(a (b c) d)".chars().collect();
+        assert_eq!(matching_paren(line.as_slice(), 8), Some(3));
+        assert_eq!(matching_paren(line.as_slice(), 11), Some(0));
+        assert_eq!(matching_paren(line.as_slice(), 5), None);
+    }
+}
@@ -17,75 +17,279 @@
 #![feature(collections)]
 #![feature(core)]
 #![feature(env)]
+#![feature(exit_status)]
+#![feature(libc)]
 #![feature(old_io)]
 #![feature(old_path)]
 #![feature(test)]
 #![feature(unicode)]
 #![feature(unsafe_destructor)]
 
-use std::old_io;
+extern crate libc;
+extern crate time;
+
 use std::env;
 
+pub mod clock;
+pub mod diagnostic;
 pub mod environment;
 pub mod eval;
+pub mod hash_table;
 pub mod heap;
+pub mod line_editor;
+pub mod port;
+pub mod pretty;
 pub mod primitives;
 pub mod read;
+pub mod record;
+pub mod repl;
 pub mod value;
 
-/// Start a Read -> Evaluate -> Print loop.
-pub fn repl(heap: &mut heap::Heap) {
-    println!("Welcome to oxischeme!");
-    println!("C-c to exit.");
-    println!("");
-
-    loop {
-        let stdin = old_io::stdio::stdin();
-        let reader = read::Read::new(stdin, heap, "stdin".to_string());
-
-        print!("oxischeme> ");
-        for (location, read_result) in reader {
-            match read_result {
-                Err(msg) => {
-                    println!("{}", msg);
-                    break;
-                },
-                Ok(form) => {
-                    match eval::evaluate(heap, &form, location) {
-                        Ok(val) => println!("{}", *val),
-                        Err(e)  => println!("{}", e),
-                    };
+/// The usage message printed by `--help`.
+static USAGE : &'static str = "\
+Usage: oxischeme [OPTIONS] [FILE ...] [-- ARG ...]
 
-                }
-            }
+Evaluate each FILE and -e EXPR in the order given. With no files or
+expressions, start the REPL.
+
+Options:
+    -e EXPR                    Evaluate EXPR and print its value.
+    -i                         Start the REPL after evaluating files and
+                               expressions.
+    --heap-size N              Allocate every kind of object in arenas of N
+                               objects.
+    --cons-capacity N          Allocate cons cells in arenas of N cells.
+    --strings-capacity N       Allocate strings in arenas of N strings.
+    --activations-capacity N   Allocate activations in arenas of N activations.
+    --procedures-capacity N    Allocate procedures in arenas of N procedures.
+    --ports-capacity N         Allocate ports in arenas of N ports.
+    --hash-tables-capacity N   Allocate hash tables in arenas of N hash tables.
+    --records-capacity N       Allocate records in arenas of N records.
+    --version                  Print the version and exit.
+    -h, --help                 Print this help and exit.
+    --                         Pass the remaining arguments to the script,
+                               where they are returned by `(command-line)`.";
+
+/// Something to evaluate, given on the command line.
+enum Source {
+    File(String),
+    Expression(String),
+}
+
+/// The parsed command line.
+struct Options {
+    sources: Vec<Source>,
+    interactive: bool,
+    cons_capacity: usize,
+    strings_capacity: usize,
+    activations_capacity: usize,
+    procedures_capacity: usize,
+    ports_capacity: usize,
+    hash_tables_capacity: usize,
+    records_capacity: usize,
+    script_args: Vec<String>,
+}
 
-            heap.collect_garbage();
-            print!("oxischeme> ");
+/// The result of parsing the command line: either options to run with, or an
+/// exit status to exit with immediately.
+type ParseResult = Result<Options, i32>;
+
+/// Parse the value of an arena capacity option.
+fn parse_capacity(option: &str, value: Option<String>) -> Result<usize, i32> {
+    let value = try!(value.ok_or_else(|| {
+        repl::report_error(format!("Error: {} requires a value", option).as_slice());
+        1
+    }));
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _              => {
+            repl::report_error(format!("Error: {} requires a positive integer, found {}",
+                                       option, value).as_slice());
+            Err(1)
+        },
+    }
+}
+
+/// Parse the given command line arguments, not including the program name.
+fn parse_args<I: Iterator<Item=String>>(mut args: I) -> ParseResult {
+    let mut options = Options {
+        sources: vec!(),
+        interactive: false,
+        cons_capacity: heap::DEFAULT_CONS_CAPACITY,
+        strings_capacity: heap::DEFAULT_STRINGS_CAPACITY,
+        activations_capacity: heap::DEFAULT_ACTIVATIONS_CAPACITY,
+        procedures_capacity: heap::DEFAULT_PROCEDURES_CAPACITY,
+        ports_capacity: heap::DEFAULT_PORTS_CAPACITY,
+        hash_tables_capacity: heap::DEFAULT_HASH_TABLES_CAPACITY,
+        records_capacity: heap::DEFAULT_RECORDS_CAPACITY,
+        script_args: vec!(),
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_slice() {
+            "-e"                     => {
+                let expr = try!(args.next().ok_or_else(|| {
+                    repl::report_error("Error: -e requires an expression");
+                    1
+                }));
+                options.sources.push(Source::Expression(expr));
+            },
+            "-i"                     => options.interactive = true,
+            "--heap-size"            => {
+                let n = try!(parse_capacity("--heap-size", args.next()));
+                options.cons_capacity = n;
+                options.strings_capacity = n;
+                options.activations_capacity = n;
+                options.procedures_capacity = n;
+                options.ports_capacity = n;
+                options.hash_tables_capacity = n;
+                options.records_capacity = n;
+            },
+            "--cons-capacity"        => {
+                options.cons_capacity = try!(parse_capacity(arg.as_slice(),
+                                                            args.next()));
+            },
+            "--strings-capacity"     => {
+                options.strings_capacity = try!(parse_capacity(arg.as_slice(),
+                                                               args.next()));
+            },
+            "--activations-capacity" => {
+                options.activations_capacity = try!(parse_capacity(arg.as_slice(),
+                                                                   args.next()));
+            },
+            "--procedures-capacity"  => {
+                options.procedures_capacity = try!(parse_capacity(arg.as_slice(),
+                                                                  args.next()));
+            },
+            "--ports-capacity"       => {
+                options.ports_capacity = try!(parse_capacity(arg.as_slice(),
+                                                             args.next()));
+            },
+            "--hash-tables-capacity" => {
+                options.hash_tables_capacity = try!(parse_capacity(arg.as_slice(),
+                                                                   args.next()));
+            },
+            "--records-capacity"     => {
+                options.records_capacity = try!(parse_capacity(arg.as_slice(),
+                                                               args.next()));
+            },
+            "--version"              => {
+                println!("oxischeme {}", env!("CARGO_PKG_VERSION"));
+                return Err(0);
+            },
+            "-h" | "--help"          => {
+                println!("{}", USAGE);
+                return Err(0);
+            },
+            "--"                     => {
+                options.script_args.extend(args);
+                break;
+            },
+            _ if arg.starts_with("-") => {
+                repl::report_error(format!("Error: unknown option {}\n\n{}",
+                                           arg, USAGE).as_slice());
+                return Err(1);
+            },
+            _                        => options.sources.push(Source::File(arg)),
         }
     }
+
+    Ok(options)
 }
 
-/// Given no arguments, start the REPL. Otherwise, treat each argument as a file
-/// path and read and evaluate each of them in turn.
+/// Read and evaluate each form from the given reader, reporting the first error
+/// on stderr. If `print` is true, print the value of each form. Returns true
+/// if there were no errors, false otherwise.
+fn evaluate_forms<R: Reader>(heap: &mut heap::Heap,
+                             mut reader: read::Read<R>,
+                             print: bool) -> bool {
+    while let Some((location, read_result)) = reader.next() {
+        match read_result {
+            Err(msg) => {
+                repl::report_read_error(&reader, msg.as_slice());
+                return false;
+            },
+            Ok(form) => {
+                match repl::evaluate_form(heap, &form, location) {
+                    None      => return false,
+                    Some(val) => {
+                        if print && *val != *heap.unspecified_symbol() {
+                            println!("{}", *val);
+                        }
+                    },
+                }
+            },
+        }
+    }
+
+    true
+}
+
+/// Read and evaluate each form in the file at the given path, reporting the
+/// first error on stderr. Returns true if there were no errors, false
+/// otherwise.
+fn evaluate_file(heap: &mut heap::Heap, file_path: &str) -> bool {
+    match read::read_from_file(file_path, heap) {
+        Ok(reader) => evaluate_forms(heap, reader, false),
+        Err(e)     => {
+            repl::report_error(format!("Error: could not read {}: {}",
+                                       file_path,
+                                       e).as_slice());
+            false
+        },
+    }
+}
+
+/// Evaluate each file and expression given on the command line in order, then
+/// start the REPL if there were none or `-i` was given. Exits with a non-zero
+/// status if there was an error.
 pub fn main() {
-    let heap = &mut heap::Heap::new();
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(status) => {
+            env::set_exit_status(status);
+            return;
+        },
+    };
 
-    let mut args_were_passed = false;
+    let heap = &mut heap::Heap::with_arenas(
+        heap::ArenaSet::new(options.cons_capacity),
+        heap::ArenaSet::new(options.strings_capacity),
+        heap::ArenaSet::new(options.activations_capacity),
+        heap::ArenaSet::new(options.procedures_capacity),
+        heap::ArenaSet::new(options.ports_capacity),
+        heap::ArenaSet::new(options.hash_tables_capacity),
+        heap::ArenaSet::new(options.records_capacity));
 
-    for file_path in env::args().skip(1) {
-        args_were_passed = true;
+    // The script's name is the file, as with `argv[0]` for a program.
+    let program = options.sources.iter()
+        .filter_map(|source| match *source {
+            Source::File(ref path) => Some(path.clone()),
+            _                      => None,
+        })
+        .next()
+        .unwrap_or_else(|| "oxischeme".to_string());
+    let mut command_line = vec!(program);
+    command_line.extend(options.script_args.into_iter());
+    heap.set_command_line(command_line);
 
-        match eval::evaluate_file(heap, file_path.as_slice()) {
-            Ok(_) => { },
-            Err(msg) => {
-                let mut stderr = old_io::stdio::stderr();
-                (write!(&mut stderr, "{}", msg)).ok().expect("IO ERROR!");
-                return;
-            }
+    for source in options.sources.iter() {
+        let ok = match *source {
+            Source::File(ref path)       => evaluate_file(heap, path.as_slice()),
+            Source::Expression(ref expr) => {
+                let reader = read::read_from_str(expr.as_slice(), heap, "-e");
+                evaluate_forms(heap, reader, true)
+            },
+        };
+
+        if !ok {
+            env::set_exit_status(heap.exit_status().unwrap_or(1));
+            return;
         }
     }
 
-    if !args_were_passed {
-        repl(heap);
+    if options.sources.is_empty() || options.interactive {
+        repl::Repl::new(heap).run();
+        env::set_exit_status(heap.exit_status().unwrap_or(0));
     }
 }
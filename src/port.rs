@@ -0,0 +1,421 @@
+// Copyright 2015 Nick Fitzgerald
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scheme ports: sources of input characters and sinks for output characters.
+//!
+//! A `Port` is a GC thing allocated on the heap, so that a port value can be
+//! shared, stored in data structures, and compared with `eq?` like any other
+//! object. Ports do not reference any other GC things.
+
+use std::default::{Default};
+use std::fmt;
+use std::hash;
+use std::old_io::{self, Buffer, BufferedReader, BufferedWriter, File, IoError,
+                  IoErrorKind, IoResult, MemReader, Writer};
+
+use heap::{ArenaPtr, GcThing, IterGcThing, Rooted, ToGcThing, Trace};
+
+/// Either a value, or a `String` describing the error.
+pub type PortResult<T> = Result<T, String>;
+
+/// Whether a port is for reading or writing.
+#[derive(Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Input,
+    Output,
+}
+
+/// The sink of an output port.
+enum Output {
+    Writer(Box<Writer + 'static>),
+    /// Output accumulated in memory, for string ports.
+    String(Vec<u8>),
+}
+
+/// A port for reading characters from, or writing characters to.
+pub struct Port {
+    direction: Direction,
+    /// Whether this port reads and writes bytes rather than characters.
+    binary: bool,
+    /// A description of the port's source or sink, e.g. a file path.
+    name: String,
+    /// The source of an open input port.
+    input: Option<Box<Buffer + 'static>>,
+    /// The sink of an open output port.
+    output: Option<Output>,
+    /// Characters that were taken from `input` and then given back, for
+    /// example by `peek-char`. The last character is the next one read.
+    pushback: Vec<char>,
+}
+
+impl Default for Port {
+    /// Do not use this method, instead allocate ports on the heap with
+    /// `Heap::allocate_port` and get back a `PortPtr`.
+    fn default() -> Port {
+        Port {
+            direction: Direction::Input,
+            binary: false,
+            name: String::new(),
+            input: None,
+            output: None,
+            pushback: vec!(),
+        }
+    }
+}
+
+/// ## `Port` Constructors
+impl Port {
+    /// Create a new input port reading from the given source.
+    pub fn new_input(name: String, input: Box<Buffer + 'static>) -> Port {
+        Port {
+            direction: Direction::Input,
+            binary: false,
+            name: name,
+            input: Some(input),
+            output: None,
+            pushback: vec!(),
+        }
+    }
+
+    /// Create a new output port writing to the given sink.
+    pub fn new_output(name: String, output: Box<Writer + 'static>) -> Port {
+        Port {
+            direction: Direction::Output,
+            binary: false,
+            name: name,
+            input: None,
+            output: Some(Output::Writer(output)),
+            pushback: vec!(),
+        }
+    }
+
+    /// Open the file at the given path for reading.
+    pub fn open_input_file(path: &str) -> PortResult<Port> {
+        let file = try!(File::open(&Path::new(path)).map_err(|e| {
+            format!("Error: could not open file {} for reading: {}", path, e)
+        }));
+        Ok(Port::new_input(path.to_string(), Box::new(BufferedReader::new(file))))
+    }
+
+    /// Create or truncate the file at the given path, and open it for writing.
+    pub fn open_output_file(path: &str) -> PortResult<Port> {
+        let file = try!(File::create(&Path::new(path)).map_err(|e| {
+            format!("Error: could not open file {} for writing: {}", path, e)
+        }));
+        Ok(Port::new_output(path.to_string(), Box::new(BufferedWriter::new(file))))
+    }
+
+    /// Create an input port reading the characters of the given string.
+    pub fn open_input_string(str: String) -> Port {
+        Port::new_input("string".to_string(), Box::new(MemReader::new(str.into_bytes())))
+    }
+
+    /// Create an output port which accumulates the characters written to it in
+    /// a string, which can be retrieved with `output_string`.
+    pub fn open_output_string() -> Port {
+        Port {
+            direction: Direction::Output,
+            binary: false,
+            name: "string".to_string(),
+            input: None,
+            output: Some(Output::String(vec!())),
+            pushback: vec!(),
+        }
+    }
+
+    /// Make this port read and write bytes rather than characters.
+    pub fn binary(mut self) -> Port {
+        self.binary = true;
+        self
+    }
+
+    /// Create an input port reading from stdin.
+    pub fn stdin() -> Port {
+        Port::new_input("stdin".to_string(), Box::new(old_io::stdio::stdin()))
+    }
+
+    /// Create an output port writing to stdout. Output is not buffered, so
+    /// that it is interleaved correctly with the REPL's own output.
+    pub fn stdout() -> Port {
+        Port::new_output("stdout".to_string(), Box::new(old_io::stdio::stdout_raw()))
+    }
+
+    /// Create an output port writing to stderr.
+    pub fn stderr() -> Port {
+        Port::new_output("stderr".to_string(), Box::new(old_io::stdio::stderr_raw()))
+    }
+}
+
+/// ## `Port` Methods
+impl Port {
+    /// Get a description of this port's source or sink.
+    pub fn name(&self) -> &str {
+        self.name.as_slice()
+    }
+
+    /// Return true if this is an input port, false otherwise.
+    pub fn is_input(&self) -> bool {
+        self.direction == Direction::Input
+    }
+
+    /// Return true if this is an output port, false otherwise.
+    pub fn is_output(&self) -> bool {
+        self.direction == Direction::Output
+    }
+
+    /// Return true if this port reads or writes bytes, false if it reads or
+    /// writes characters.
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    /// Return true if this port has not been closed, false otherwise.
+    pub fn is_open(&self) -> bool {
+        self.input.is_some() || self.output.is_some()
+    }
+
+    /// Close this port, flushing any buffered output. Closing a port that is
+    /// already closed has no effect.
+    pub fn close(&mut self) -> PortResult<()> {
+        self.input = None;
+        self.pushback.clear();
+        if let Some(Output::Writer(mut output)) = self.output.take() {
+            try!(output.flush().map_err(|e| self.io_error(e)));
+        }
+        Ok(())
+    }
+
+    /// Read the next character, or return `None` at the end of input.
+    pub fn read_char(&mut self) -> PortResult<Option<char>> {
+        if let Some(c) = self.pushback.pop() {
+            return Ok(Some(c));
+        }
+
+        let result = try!(self.input(false)).read_char();
+        match result {
+            Ok(c)                                          => Ok(Some(c)),
+            Err(ref e) if e.kind == IoErrorKind::EndOfFile => Ok(None),
+            Err(e)                                         => Err(self.io_error(e)),
+        }
+    }
+
+    /// Get the next character without consuming it, or `None` at the end of
+    /// input.
+    pub fn peek_char(&mut self) -> PortResult<Option<char>> {
+        let c = try!(self.read_char());
+        if let Some(c) = c {
+            self.unread_char(c);
+        }
+        Ok(c)
+    }
+
+    /// Give back a character so that it is the next one read.
+    pub fn unread_char(&mut self, c: char) {
+        self.pushback.push(c);
+    }
+
+    /// Read characters up to the next newline or the end of input, not
+    /// including the newline. Returns `None` if already at the end of input.
+    pub fn read_line(&mut self) -> PortResult<Option<String>> {
+        let mut line = String::new();
+        loop {
+            match try!(self.read_char()) {
+                None if line.is_empty() => return Ok(None),
+                None | Some('\n')       => return Ok(Some(line)),
+                Some(c)                 => line.push(c),
+            }
+        }
+    }
+
+    /// Read up to `k` characters, stopping early at the end of input. Returns
+    /// `None` if already at the end of input.
+    pub fn read_string(&mut self, k: usize) -> PortResult<Option<String>> {
+        let mut str = String::new();
+        for _ in range(0, k) {
+            match try!(self.read_char()) {
+                Some(c) => str.push(c),
+                None    => break,
+            }
+        }
+        Ok(if str.is_empty() && k > 0 { None } else { Some(str) })
+    }
+
+    /// Read the next byte from a binary port, or return `None` at the end of
+    /// input.
+    pub fn read_u8(&mut self) -> PortResult<Option<u8>> {
+        let result = try!(self.input(true)).read_byte();
+        match result {
+            Ok(b)                                          => Ok(Some(b)),
+            Err(ref e) if e.kind == IoErrorKind::EndOfFile => Ok(None),
+            Err(e)                                         => Err(self.io_error(e)),
+        }
+    }
+
+    /// Get the next byte from a binary port without consuming it, or `None` at
+    /// the end of input.
+    pub fn peek_u8(&mut self) -> PortResult<Option<u8>> {
+        let result = try!(self.input(true)).fill_buf().map(|buf| buf[0]);
+        match result {
+            Ok(b)                                          => Ok(Some(b)),
+            Err(ref e) if e.kind == IoErrorKind::EndOfFile => Ok(None),
+            Err(e)                                         => Err(self.io_error(e)),
+        }
+    }
+
+    /// Write the given string.
+    pub fn write_str(&mut self, str: &str) -> PortResult<()> {
+        let result = try!(self.output(false)).write_str(str);
+        result.map_err(|e| self.io_error(e))
+    }
+
+    /// Write the given character.
+    pub fn write_char(&mut self, c: char) -> PortResult<()> {
+        let result = try!(self.output(false)).write_char(c);
+        result.map_err(|e| self.io_error(e))
+    }
+
+    /// Write the given byte to a binary port.
+    pub fn write_u8(&mut self, b: u8) -> PortResult<()> {
+        let result = try!(self.output(true)).write_u8(b);
+        result.map_err(|e| self.io_error(e))
+    }
+
+    /// Get the characters written so far to an open string output port.
+    pub fn output_string(&self) -> PortResult<String> {
+        match self.output {
+            Some(Output::String(ref bytes)) => {
+                Ok(String::from_utf8_lossy(bytes.as_slice()).into_owned())
+            },
+            _                               => {
+                Err(format!("Error: {} is not an open string output port", self))
+            },
+        }
+    }
+
+    /// Flush any buffered output.
+    pub fn flush(&mut self) -> PortResult<()> {
+        let binary = self.binary;
+        let result = try!(self.output(binary)).flush();
+        result.map_err(|e| self.io_error(e))
+    }
+
+    /// Get the source of this port, checking that it is an open input port
+    /// and whether it is binary.
+    fn input(&mut self, binary: bool) -> PortResult<&mut Box<Buffer + 'static>> {
+        if self.is_output() {
+            return Err(format!("Error: cannot read from output port {}", self.name));
+        }
+        try!(self.check_binary(binary));
+        let name = self.name.clone();
+        self.input.as_mut()
+            .ok_or_else(|| format!("Error: cannot read from closed port {}", name))
+    }
+
+    /// Get the sink of this port, checking that it is an open output port and
+    /// whether it is binary.
+    fn output(&mut self, binary: bool) -> PortResult<&mut Writer> {
+        if self.is_input() {
+            return Err(format!("Error: cannot write to input port {}", self.name));
+        }
+        try!(self.check_binary(binary));
+        match self.output {
+            Some(Output::Writer(ref mut writer)) => Ok(&mut **writer),
+            Some(Output::String(ref mut bytes))  => Ok(bytes as &mut Writer),
+            None                                 => {
+                Err(format!("Error: cannot write to closed port {}", self.name))
+            },
+        }
+    }
+
+    fn check_binary(&self, binary: bool) -> PortResult<()> {
+        match (self.binary, binary) {
+            (true, false) => Err(format!("Error: cannot use binary port {} for characters",
+                                         self.name)),
+            (false, true) => Err(format!("Error: cannot use textual port {} for bytes",
+                                         self.name)),
+            _             => Ok(()),
+        }
+    }
+
+    fn io_error(&self, e: IoError) -> String {
+        format!("Error: I/O error on port {}: {}", self.name, e)
+    }
+}
+
+impl Trace for Port {
+    fn trace(&self) -> IterGcThing {
+        // Ports don't hold any strong references to other `GcThing`s.
+        vec!().into_iter()
+    }
+}
+
+impl hash::Hash for Port {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<{}{}-port {}>",
+               if self.binary { "binary-" } else { "" },
+               if self.is_input() { "input" } else { "output" },
+               self.name)
+    }
+}
+
+/// A pointer to a `Port` on the heap.
+pub type PortPtr = ArenaPtr<Port>;
+
+impl ToGcThing for PortPtr {
+    fn to_gc_thing(&self) -> Option<GcThing> {
+        Some(GcThing::from_port_ptr(*self))
+    }
+}
+
+/// A rooted pointer to a `Port` on the heap.
+pub type RootedPortPtr = Rooted<PortPtr>;
+
+/// An adapter for reading a port's characters with the `Reader` interface, e.g.
+/// to `read` data from it. Each call to `read` yields exactly one character, so
+/// that a `BufferedReader` around the adapter never takes more characters from
+/// the port than it is asked for.
+pub struct PortReader {
+    port: PortPtr,
+}
+
+impl PortReader {
+    /// Create a new `PortReader`. The port must be kept rooted for as long as
+    /// the `PortReader` is in use.
+    pub fn new(port: PortPtr) -> PortReader {
+        PortReader {
+            port: port,
+        }
+    }
+}
+
+impl Reader for PortReader {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        match self.port.read_char() {
+            Ok(Some(c)) => Ok(c.encode_utf8(buf)
+                              .expect("A buffer should have room for one character")),
+            Ok(None)    => Err(old_io::standard_error(IoErrorKind::EndOfFile)),
+            Err(e)      => Err(IoError {
+                kind: IoErrorKind::OtherIoError,
+                desc: "error reading from port",
+                detail: Some(e),
+            }),
+        }
+    }
+}
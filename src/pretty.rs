@@ -0,0 +1,353 @@
+// Copyright 2015 Nick Fitzgerald
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pretty printing values over multiple lines, to fit within a given width.
+//!
+//! Any list that fits on the rest of its line is printed on one line, exactly
+//! as `write` would print it. Lists that don't fit are broken over multiple
+//! lines, with their elements indented according to the kind of form:
+//!
+//!     (define (fact n)
+//!       (if (= n 0)
+//!           1
+//!           (* n (fact (- n 1)))))
+//!
+//! Definitions, `lambda`s and `let`s indent their body by two spaces; `if`,
+//! `cond` and ordinary applications line their operands up under the first
+//! operand; and lists that don't start with a symbol line up all of their
+//! elements. Cyclic structure is printed with datum labels, as with `write`.
+
+use std::collections::{HashMap, HashSet};
+use std::iter::{self, AdditiveIterator};
+
+use record::{RecordPtr};
+use value::{find_datum_labels, ConsPtr, Value};
+
+/// The width to pretty print within when there is no better width to use,
+/// such as the width of the terminal.
+pub static DEFAULT_WIDTH : usize = 80;
+
+/// Forms whose first operand goes on the same line as the operator, and whose
+/// remaining operands are a body indented by two spaces.
+static BODY_FORMS : [&'static str; 8] = [
+    "define", "lambda", "let", "let*", "letrec", "letrec*", "when", "unless"
+];
+
+/// Pretty print the given value, breaking lists over multiple lines so that
+/// the result fits within `width` columns where possible.
+pub fn pretty_print(val: &Value, width: usize) -> String {
+    let mut builder = DocBuilder {
+        labels: find_datum_labels(val, false).into_iter()
+            .map(|cons| (cons, None))
+            .collect(),
+        next_label: 0,
+        records: HashSet::new(),
+    };
+    let doc = builder.build(val);
+
+    let mut out = String::new();
+    layout(&doc, 0, width, &mut out);
+    out
+}
+
+/// A value laid out as a tree of atoms and lists, with its datum labels
+/// resolved.
+enum Doc {
+    /// Anything that is always printed the same way on a single line, e.g. a
+    /// number, a symbol, or a datum label reference like `#0#`.
+    Atom(String),
+    List(List),
+}
+
+/// A list within a `Doc`.
+struct List {
+    /// The datum label definition for this list, e.g. `#0=`, or the empty
+    /// string.
+    label: String,
+    /// The name of the symbol at the start of the list, if any.
+    head: Option<String>,
+    items: Vec<Doc>,
+    /// The final cdr of an improper list.
+    tail: Option<Box<Doc>>,
+    /// The number of columns this list takes up when printed on one line.
+    width: usize,
+}
+
+impl List {
+    /// Create a new `List`, computing its single line width from the widths of
+    /// its items and tail.
+    fn new(label: String, head: Option<String>, items: Vec<Doc>,
+           tail: Option<Box<Doc>>) -> List {
+        let mut width = label.chars().count() + 2;
+        width += items.iter().map(|item| item.width()).sum();
+        width += items.len() - 1;
+        if let Some(ref tail) = tail {
+            width += 3 + tail.width();
+        }
+
+        List {
+            label: label,
+            head: head,
+            items: items,
+            tail: tail,
+            width: width,
+        }
+    }
+}
+
+impl Doc {
+    /// Get the number of columns this document takes up when printed on one
+    /// line.
+    fn width(&self) -> usize {
+        match *self {
+            Doc::Atom(ref str)  => str.chars().count(),
+            Doc::List(ref list) => list.width,
+        }
+    }
+
+    /// Append the single line representation of this document to `out`.
+    fn write_flat(&self, out: &mut String) {
+        match *self {
+            Doc::Atom(ref str) => out.push_str(str.as_slice()),
+            Doc::List(ref list) => {
+                out.push_str(list.label.as_slice());
+                out.push('(');
+                for (i, item) in list.items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(' ');
+                    }
+                    item.write_flat(out);
+                }
+                if let Some(ref tail) = list.tail {
+                    out.push_str(" . ");
+                    tail.write_flat(out);
+                }
+                out.push(')');
+            },
+        }
+    }
+}
+
+/// The state for building a `Doc`: which pairs need datum labels, and the
+/// labels assigned to them so far.
+struct DocBuilder {
+    labels: HashMap<ConsPtr, Option<u64>>,
+    next_label: u64,
+    /// The records currently being built, so that a record that contains
+    /// itself is abbreviated rather than built forever.
+    records: HashSet<RecordPtr>,
+}
+
+impl DocBuilder {
+    /// Build the `Doc` for the given value.
+    fn build(&mut self, val: &Value) -> Doc {
+        let cons = match *val {
+            Value::Pair(cons)                          => cons,
+            Value::Record(record) if !record.is_type() => return self.build_record(record),
+            ref atom                                   => return Doc::Atom(format!("{}", atom)),
+        };
+
+        let label = match self.labels.get(&cons).map(|l| *l) {
+            Some(Some(n)) => return Doc::Atom(format!("#{}#", n)),
+            Some(None)    => {
+                let n = self.next_label;
+                self.next_label += 1;
+                self.labels.insert(cons, Some(n));
+                format!("#{}=", n)
+            },
+            None          => String::new(),
+        };
+
+        let head = match cons.car {
+            Value::Symbol(ref name) => Some(name.as_slice().to_string()),
+            _                       => None,
+        };
+
+        let mut items = vec!(self.build(&cons.car));
+        let mut rest = cons.cdr;
+        while let Value::Pair(cdr) = rest {
+            // A labeled pair in the cdr chain must be printed as an improper
+            // tail, so that it can be given its label.
+            if self.labels.contains_key(&cdr) {
+                break;
+            }
+            items.push(self.build(&cdr.car));
+            rest = cdr.cdr;
+        }
+        let tail = match rest {
+            Value::EmptyList => None,
+            ref val          => Some(Box::new(self.build(val))),
+        };
+
+        Doc::List(List::new(label, head, items, tail))
+    }
+
+    /// Build the `Doc` for the given record instance. Records are always
+    /// printed on one line, but the pairs in their fields share datum labels
+    /// with the rest of the value, rather than being numbered afresh.
+    fn build_record(&mut self, record: RecordPtr) -> Doc {
+        if self.records.contains(&record) {
+            return Doc::Atom(format!("#<record {} ...>", record.type_name()));
+        }
+
+        self.records.insert(record);
+        let mut out = format!("#<record {}", record.type_name());
+        for (name, val) in record.field_names().iter().zip(record.fields().iter()) {
+            out.push_str(format!(" {}: ", name).as_slice());
+            self.build(val).write_flat(&mut out);
+        }
+        self.records.remove(&record);
+        out.push('>');
+        Doc::Atom(out)
+    }
+}
+
+/// Append the given document, which starts at the given column, to `out` and
+/// return the column that it ends at.
+fn layout(doc: &Doc, column: usize, width: usize, out: &mut String) -> usize {
+    let list = match *doc {
+        Doc::List(ref list) if column + list.width > width => list,
+        _                                                   => {
+            doc.write_flat(out);
+            return column + doc.width();
+        },
+    };
+
+    out.push_str(list.label.as_slice());
+    out.push('(');
+    let open = column + list.label.chars().count() + 1;
+
+    // The number of items to put on the first line, and the column to indent
+    // the rest of the items to.
+    let (first_line, indent) = match list.head {
+        Some(ref head) if BODY_FORMS.contains(&head.as_slice()) => {
+            let named_let = head.as_slice() == "let" && match list.items.get(1) {
+                Some(&Doc::Atom(_)) => true,
+                _                   => false,
+            };
+            (if named_let { 3 } else { 2 }, open + 1)
+        },
+        Some(ref head) if open + head.chars().count() + 1 <= width / 2 => {
+            (2, open + head.chars().count() + 1)
+        },
+        _                                                       => (1, open),
+    };
+
+    let mut column = open;
+    for (i, item) in list.items.iter().enumerate() {
+        if i == 0 {
+            column = layout(item, column, width, out);
+        } else if i < first_line {
+            out.push(' ');
+            column = layout(item, column + 1, width, out);
+        } else {
+            newline(out, indent);
+            column = layout(item, indent, width, out);
+        }
+    }
+
+    if let Some(ref tail) = list.tail {
+        newline(out, indent);
+        out.push_str(". ");
+        column = layout(&**tail, indent + 2, width, out);
+    }
+
+    out.push(')');
+    column + 1
+}
+
+/// Start a new line, indented to the given column.
+fn newline(out: &mut String, indent: usize) {
+    out.push('\n');
+    out.extend(iter::repeat(' ').take(indent));
+}
+
+// TESTS -----------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heap::{Heap};
+    use read::{read_from_str};
+
+    fn pretty(input: &str, width: usize) -> String {
+        let heap = &mut Heap::new();
+        let (_, result) = read_from_str(input, heap, "test_pretty_print").next()
+            .expect("Should read a datum");
+        let val = result.ok().expect("Should not get a read error");
+        pretty_print(&*val, width)
+    }
+
+    #[test]
+    fn test_pretty_print_fits() {
+        assert_eq!(pretty("(a (b c) \"d\")", 80), "(a (b c) \"d\")");
+    }
+
+    #[test]
+    fn test_pretty_print_define() {
+        assert_eq!(pretty("(define (fact n) (if (= n 0) 1 (* n (fact (- n 1)))))", 30),
+                   "(define (fact n)\n  \
+                    (if (= n 0)\n      \
+                    1\n      \
+                    (* n (fact (- n 1)))))");
+    }
+
+    #[test]
+    fn test_pretty_print_named_let_and_cond() {
+        assert_eq!(pretty("(let loop ((i 0)) (cond ((= i 10) i) (else (loop (+ i 1)))))", 30),
+                   "(let loop ((i 0))\n  \
+                    (cond ((= i 10) i)\n        \
+                    (else (loop (+ i 1)))))");
+    }
+
+    #[test]
+    fn test_pretty_print_data() {
+        assert_eq!(pretty("((1 2 3) (4 5 6) (7 8 9) . 10)", 12),
+                   "((1 2 3)\n \
+                    (4 5 6)\n \
+                    (7 8 9)\n \
+                    . 10)");
+    }
+
+    #[test]
+    fn test_pretty_print_cycle_in_record() {
+        use record::{Record};
+        use value::{list, Value};
+
+        let heap = &mut Heap::new();
+        let read = |heap: &mut Heap, input: &str| {
+            let (_, result) = read_from_str(input, heap, "test_pretty_print").next()
+                .expect("Should read a datum");
+            result.ok().expect("Should not get a read error")
+        };
+        let outer_cycle = read(heap, "#0=(a . #0#)");
+        let inner_cycle = read(heap, "#0=(b . #0#)");
+        let record_type = heap.allocate_record(Record::new_type("<box>".to_string(),
+                                                                vec!("value".to_string())));
+        let record = Value::new_record(heap, Record::new_instance(*record_type,
+                                                                  vec!(*inner_cycle)));
+        let val = list(heap, &[outer_cycle, record]);
+        assert_eq!(pretty_print(&*val, 80),
+                   "(#0=(a . #0#) #<record box value: #1=(b . #1#)>)");
+    }
+
+    #[test]
+    fn test_pretty_print_cycle() {
+        assert_eq!(pretty("#0=(aaaa bbbb cccc . #0#)", 10),
+                   "#0=(aaaa\n    \
+                    bbbb\n    \
+                    cccc\n    \
+                    . #0#)");
+    }
+}
@@ -16,9 +16,13 @@
 
 use environment::{ActivationPtr, Environment};
 use eval::{apply_invocation, Trampoline, TrampolineResult};
-use heap::{Heap, Rooted};
+use hash_table::{Equivalence, HashTable, RootedHashTablePtr};
+use heap::{Handler, Heap, Rooted};
+use port::{Port, PortReader, PortResult, RootedPortPtr};
+use pretty;
 use read::{Read};
-use value::{RootedValue, Value};
+use record::{Record, RootedRecordPtr};
+use value::{PrintStyle, RootedValue, SchemeResult, Value};
 
 /// The function signature for primitives.
 pub type PrimitiveFunction = fn(&mut Heap, Vec<RootedValue>) -> TrampolineResult;
@@ -90,6 +94,1026 @@ fn length(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
     }
 }
 
+/// Call the given procedure with the given arguments, running it to
+/// completion.
+fn call_procedure(heap: &mut Heap,
+                  procedure: &RootedValue,
+                  args: Vec<RootedValue>) -> SchemeResult {
+    apply_invocation(heap, procedure, args)
+        .and_then(|trampoline| trampoline.run(heap))
+}
+
+/// Call the given procedure with the given arguments, and return true if its
+/// result is anything other than `#f`.
+fn call_predicate(heap: &mut Heap,
+                  procedure: &RootedValue,
+                  args: Vec<RootedValue>) -> Result<bool, String> {
+    let result = try!(call_procedure(heap, procedure, args));
+    Ok(*result != Value::new_boolean(false))
+}
+
+/// Collect the elements of the given proper list argument of the primitive
+/// with the given name.
+fn list_elements(heap: &mut Heap,
+                 list: &RootedValue,
+                 name: &str) -> Result<Vec<RootedValue>, String> {
+    list.iter()
+        .map(|result| {
+            result
+                .map(|val| Rooted::new(heap, val))
+                .map_err(|_| format!("Error: `{}` requires a proper list, got {}",
+                                     name, **list))
+        })
+        .collect()
+}
+
+/// Make a list of the given values, ending in `tail` rather than the empty
+/// list.
+fn list_with_tail(heap: &mut Heap,
+                  values: &[RootedValue],
+                  tail: &RootedValue) -> RootedValue {
+    let mut result = tail.clone();
+    for val in values.iter().rev() {
+        result = Value::new_pair(heap, val, &result);
+    }
+    result
+}
+
+/// Get the non-negative integer index argument of the primitive with the
+/// given name.
+fn index_arg(arg: &RootedValue, name: &str) -> Result<usize, String> {
+    match arg.to_integer() {
+        Some(k) if k >= 0 => Ok(k as usize),
+        _                 => Err(format!("Error: `{}` requires a non-negative integer index, got {}",
+                                         name, **arg)),
+    }
+}
+
+/// Get the sublist of `list` after dropping its first `k` pairs.
+fn drop_pairs(heap: &mut Heap,
+              list: &RootedValue,
+              k: usize,
+              name: &str) -> SchemeResult {
+    let mut rest = list.clone();
+    for _ in range(0, k) {
+        rest = try!(rest.cdr(heap).ok_or(
+            format!("Error: index {} is out of bounds for `{}` of {}", k, name, **list)));
+    }
+    Ok(rest)
+}
+
+/// Get the arguments to call a procedure with for each index of the lists in
+/// `args`, starting at `args[first_list]`, stopping at the end of the shortest
+/// list. There must be at least one list.
+fn list_arguments(heap: &mut Heap,
+                  args: &[RootedValue],
+                  first_list: usize,
+                  name: &str) -> Result<Vec<Vec<RootedValue>>, String> {
+    if args.len() <= first_list {
+        return Err(format!("Error: bad arguments to `{}`", name));
+    }
+    let lists : Vec<Vec<RootedValue>> = try!(args.slice_from(first_list).iter()
+        .map(|list| list_elements(heap, list, name))
+        .collect());
+    let len = lists.iter().map(|list| list.len()).min().unwrap_or(0);
+    Ok(range(0, len)
+       .map(|i| lists.iter().map(|list| list[i].clone()).collect())
+       .collect())
+}
+
+/// Call the procedure in `args[0]` with the elements of the lists in the rest
+/// of `args` at each index in turn, stopping at the end of the shortest list,
+/// and return the results.
+fn map_lists(heap: &mut Heap,
+             args: &[RootedValue],
+             name: &str) -> Result<Vec<RootedValue>, String> {
+    let arguments = try!(list_arguments(heap, args, 1, name));
+    arguments.into_iter()
+        .map(|call_args| call_procedure(heap, &args[0], call_args))
+        .collect()
+}
+
+fn map(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    let results = try!(map_lists(heap, args.as_slice(), "map"));
+    Ok(Trampoline::Value(value::list(heap, results.as_slice())))
+}
+
+fn for_each(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    try!(map_lists(heap, args.as_slice(), "for-each"));
+    Ok(Trampoline::Value(heap.unspecified_symbol()))
+}
+
+fn append(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() == 0 {
+        return Ok(Trampoline::Value(Rooted::new(heap, Value::EmptyList)));
+    }
+    let mut result = args[args.len() - 1].clone();
+    for list in args.slice_to(args.len() - 1).iter().rev() {
+        let elements = try!(list_elements(heap, list, "append"));
+        result = list_with_tail(heap, elements.as_slice(), &result);
+    }
+    Ok(Trampoline::Value(result))
+}
+
+fn append_bang(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let mut result = Rooted::new(heap, Value::EmptyList);
+    // The last pair of the result so far, whose cdr the next argument is
+    // spliced onto.
+    let mut last_pair : Option<RootedValue> = None;
+
+    for (i, arg) in args.iter().enumerate() {
+        if **arg == Value::EmptyList {
+            continue;
+        }
+
+        match last_pair {
+            Some(ref mut pair) => {
+                if let Value::Pair(ref mut cons) = **pair {
+                    cons.set_cdr(arg);
+                }
+            },
+            None               => result = arg.clone(),
+        }
+
+        if i + 1 < args.len() {
+            try!(list_elements(heap, arg, "append!"));
+            let mut pair = arg.clone();
+            while let Some(cdr) = pair.cdr(heap) {
+                if !cdr.is_pair() {
+                    break;
+                }
+                pair = cdr;
+            }
+            last_pair = Some(pair);
+        }
+    }
+
+    Ok(Trampoline::Value(result))
+}
+
+fn reverse(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref list] = args.as_slice() {
+        let elements = try!(list_elements(heap, list, "reverse"));
+        let mut result = Rooted::new(heap, Value::EmptyList);
+        for val in elements.iter() {
+            result = Value::new_pair(heap, val, &result);
+        }
+        Ok(Trampoline::Value(result))
+    } else {
+        Err("Error: bad arguments to `reverse`".to_string())
+    }
+}
+
+fn list_tail(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref list, ref k] = args.as_slice() {
+        let k = try!(index_arg(k, "list-tail"));
+        drop_pairs(heap, list, k, "list-tail").map(|v| Trampoline::Value(v))
+    } else {
+        Err("Error: bad arguments to `list-tail`".to_string())
+    }
+}
+
+fn list_ref(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref list, ref k] = args.as_slice() {
+        let k = try!(index_arg(k, "list-ref"));
+        let rest = try!(drop_pairs(heap, list, k, "list-ref"));
+        rest.car(heap)
+            .ok_or(format!("Error: index {} is out of bounds for `list-ref` of {}",
+                           k, **list))
+            .map(|v| Trampoline::Value(v))
+    } else {
+        Err("Error: bad arguments to `list-ref`".to_string())
+    }
+}
+
+fn list_copy(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref obj] = args.as_slice() {
+        let mut elements = vec!();
+        let mut rest = obj.clone();
+        while let Some(car) = rest.car(heap) {
+            elements.push(car);
+            rest = rest.cdr(heap).expect("Should be a pair");
+        }
+        Ok(Trampoline::Value(list_with_tail(heap, elements.as_slice(), &rest)))
+    } else {
+        Err("Error: bad arguments to `list-copy`".to_string())
+    }
+}
+
+/// Get the last pair of the given list, for the primitive with the given name.
+fn find_last_pair(heap: &mut Heap, list: &RootedValue, name: &str) -> SchemeResult {
+    if !list.is_pair() {
+        return Err(format!("Error: cannot take `{}` of non-pair: {}", name, **list));
+    }
+    let mut pair = list.clone();
+    while let Some(cdr) = pair.cdr(heap) {
+        if !cdr.is_pair() {
+            break;
+        }
+        pair = cdr;
+    }
+    Ok(pair)
+}
+
+fn last_pair(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref list] = args.as_slice() {
+        find_last_pair(heap, list, "last-pair").map(|v| Trampoline::Value(v))
+    } else {
+        Err("Error: bad arguments to `last-pair`".to_string())
+    }
+}
+
+/// Find the first sublist of `list` whose car satisfies `matches`, for the
+/// primitive with the given name.
+fn find_sublist<F>(heap: &mut Heap,
+                   list: &RootedValue,
+                   name: &str,
+                   mut matches: F) -> Result<Option<RootedValue>, String>
+    where F: FnMut(&mut Heap, &RootedValue) -> Result<bool, String>
+{
+    let mut rest = list.clone();
+    while let Some(car) = rest.car(heap) {
+        if try!(matches(heap, &car)) {
+            return Ok(Some(rest));
+        }
+        rest = rest.cdr(heap).expect("Should be a pair");
+    }
+
+    if *rest == Value::EmptyList {
+        Ok(None)
+    } else {
+        Err(format!("Error: `{}` requires a proper list, got {}", name, **list))
+    }
+}
+
+/// Find the first sublist of `list` whose car matches `obj`, or `#f`, for
+/// `memq`, `member` and friends.
+fn find_member<F>(heap: &mut Heap,
+                  obj: &RootedValue,
+                  list: &RootedValue,
+                  name: &str,
+                  mut matches: F) -> TrampolineResult
+    where F: FnMut(&mut Heap, &RootedValue, &RootedValue) -> Result<bool, String>
+{
+    let found = try!(find_sublist(heap, list, name, |heap, car| matches(heap, obj, car)));
+    Ok(Trampoline::Value(match found {
+        Some(sublist) => sublist,
+        None          => Rooted::new(heap, Value::new_boolean(false)),
+    }))
+}
+
+/// Find the first entry of the association list `alist` whose key matches
+/// `obj`, or `#f`, for `assq`, `assoc` and friends.
+fn find_association<F>(heap: &mut Heap,
+                       obj: &RootedValue,
+                       alist: &RootedValue,
+                       name: &str,
+                       mut matches: F) -> TrampolineResult
+    where F: FnMut(&mut Heap, &RootedValue, &RootedValue) -> Result<bool, String>
+{
+    let found = try!(find_sublist(heap, alist, name, |heap, entry| {
+        let key = try!(entry.car(heap).ok_or(
+            format!("Error: `{}` requires an association list, got {}", name, **alist)));
+        matches(heap, obj, &key)
+    }));
+    Ok(Trampoline::Value(match found {
+        Some(sublist) => sublist.car(heap).expect("Should be a pair"),
+        None          => Rooted::new(heap, Value::new_boolean(false)),
+    }))
+}
+
+/// Compare two values with `eq?`.
+fn is_eq(_: &mut Heap, a: &RootedValue, b: &RootedValue) -> Result<bool, String> {
+    Ok(**a == **b)
+}
+
+/// Compare two values with `eqv?`.
+fn is_eqv(_: &mut Heap, a: &RootedValue, b: &RootedValue) -> Result<bool, String> {
+    Ok(a.eqv(&**b))
+}
+
+/// Compare two values with `equal?`.
+fn is_equal(_: &mut Heap, a: &RootedValue, b: &RootedValue) -> Result<bool, String> {
+    Ok(a.equal(&**b))
+}
+
+fn memq(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref obj, ref list] = args.as_slice() {
+        find_member(heap, obj, list, "memq", is_eq)
+    } else {
+        Err("Error: bad arguments to `memq`".to_string())
+    }
+}
+
+fn memv(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref obj, ref list] = args.as_slice() {
+        find_member(heap, obj, list, "memv", is_eqv)
+    } else {
+        Err("Error: bad arguments to `memv`".to_string())
+    }
+}
+
+fn member(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    match args.as_slice() {
+        [ref obj, ref list]                => find_member(heap, obj, list, "member", is_equal),
+        [ref obj, ref list, ref procedure] => {
+            find_member(heap, obj, list, "member", |heap, a, b| {
+                call_predicate(heap, procedure, vec!(a.clone(), b.clone()))
+            })
+        },
+        _                                  => Err("Error: bad arguments to `member`".to_string()),
+    }
+}
+
+fn assq(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref obj, ref alist] = args.as_slice() {
+        find_association(heap, obj, alist, "assq", is_eq)
+    } else {
+        Err("Error: bad arguments to `assq`".to_string())
+    }
+}
+
+fn assv(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref obj, ref alist] = args.as_slice() {
+        find_association(heap, obj, alist, "assv", is_eqv)
+    } else {
+        Err("Error: bad arguments to `assv`".to_string())
+    }
+}
+
+fn assoc(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    match args.as_slice() {
+        [ref obj, ref alist]                => find_association(heap, obj, alist, "assoc", is_equal),
+        [ref obj, ref alist, ref procedure] => {
+            find_association(heap, obj, alist, "assoc", |heap, a, b| {
+                call_predicate(heap, procedure, vec!(a.clone(), b.clone()))
+            })
+        },
+        _                                   => Err("Error: bad arguments to `assoc`".to_string()),
+    }
+}
+
+/// Make a list of two values, standing in for returning multiple values from
+/// `partition` and `split-at`.
+fn two_lists(heap: &mut Heap, first: &[RootedValue], second: &[RootedValue]) -> RootedValue {
+    use value;
+    let first = value::list(heap, first);
+    let second = value::list(heap, second);
+    value::list(heap, &[first, second])
+}
+
+/// Split the elements of `list` into those that satisfy `pred` and those that
+/// don't, for the primitive with the given name.
+fn partition_list(heap: &mut Heap,
+                  pred: &RootedValue,
+                  list: &RootedValue,
+                  name: &str) -> Result<(Vec<RootedValue>, Vec<RootedValue>), String> {
+    let mut matching = vec!();
+    let mut rest = vec!();
+    for val in try!(list_elements(heap, list, name)).into_iter() {
+        if try!(call_predicate(heap, pred, vec!(val.clone()))) {
+            matching.push(val);
+        } else {
+            rest.push(val);
+        }
+    }
+    Ok((matching, rest))
+}
+
+fn filter(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    if let [ref pred, ref list] = args.as_slice() {
+        let (matching, _) = try!(partition_list(heap, pred, list, "filter"));
+        Ok(Trampoline::Value(value::list(heap, matching.as_slice())))
+    } else {
+        Err("Error: bad arguments to `filter`".to_string())
+    }
+}
+
+fn remove(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    if let [ref pred, ref list] = args.as_slice() {
+        let (_, rest) = try!(partition_list(heap, pred, list, "remove"));
+        Ok(Trampoline::Value(value::list(heap, rest.as_slice())))
+    } else {
+        Err("Error: bad arguments to `remove`".to_string())
+    }
+}
+
+/// `(partition pred list)`: split `list` into the elements that satisfy `pred`
+/// and those that don't.
+///
+/// SRFI-1 returns the two lists as multiple values, but we don't have multiple
+/// values, so this returns them as a two-element list instead.
+fn partition(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref pred, ref list] = args.as_slice() {
+        let (matching, rest) = try!(partition_list(heap, pred, list, "partition"));
+        Ok(Trampoline::Value(two_lists(heap, matching.as_slice(), rest.as_slice())))
+    } else {
+        Err("Error: bad arguments to `partition`".to_string())
+    }
+}
+
+fn reduce(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref f, ref ridentity, ref list] = args.as_slice() {
+        let elements = try!(list_elements(heap, list, "reduce"));
+        let mut elements = elements.into_iter();
+        let mut acc = match elements.next() {
+            Some(first) => first,
+            None        => return Ok(Trampoline::Value(ridentity.clone())),
+        };
+        for val in elements {
+            acc = try!(call_procedure(heap, f, vec!(val, acc)));
+        }
+        Ok(Trampoline::Value(acc))
+    } else {
+        Err("Error: bad arguments to `reduce`".to_string())
+    }
+}
+
+fn fold(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let arguments = try!(list_arguments(heap, args.as_slice(), 2, "fold"));
+    let mut acc = args[1].clone();
+    for mut call_args in arguments.into_iter() {
+        call_args.push(acc);
+        acc = try!(call_procedure(heap, &args[0], call_args));
+    }
+    Ok(Trampoline::Value(acc))
+}
+
+fn fold_right(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let arguments = try!(list_arguments(heap, args.as_slice(), 2, "fold-right"));
+    let mut acc = args[1].clone();
+    for mut call_args in arguments.into_iter().rev() {
+        call_args.push(acc);
+        acc = try!(call_procedure(heap, &args[0], call_args));
+    }
+    Ok(Trampoline::Value(acc))
+}
+
+/// Remove the elements of `list` that match `obj`, for `delete`.
+fn delete_matching<F>(heap: &mut Heap,
+                      obj: &RootedValue,
+                      list: &RootedValue,
+                      mut matches: F) -> TrampolineResult
+    where F: FnMut(&mut Heap, &RootedValue, &RootedValue) -> Result<bool, String>
+{
+    use value;
+    let mut kept = vec!();
+    for val in try!(list_elements(heap, list, "delete")).into_iter() {
+        if !try!(matches(heap, obj, &val)) {
+            kept.push(val);
+        }
+    }
+    Ok(Trampoline::Value(value::list(heap, kept.as_slice())))
+}
+
+fn delete(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    match args.as_slice() {
+        [ref obj, ref list]                => delete_matching(heap, obj, list, is_equal),
+        [ref obj, ref list, ref procedure] => {
+            delete_matching(heap, obj, list, |heap, a, b| {
+                call_predicate(heap, procedure, vec!(a.clone(), b.clone()))
+            })
+        },
+        _                                  => Err("Error: bad arguments to `delete`".to_string()),
+    }
+}
+
+/// Remove all but the first of each group of matching elements of `list`,
+/// for `delete-duplicates`.
+fn delete_duplicates_matching<F>(heap: &mut Heap,
+                                 list: &RootedValue,
+                                 mut matches: F) -> TrampolineResult
+    where F: FnMut(&mut Heap, &RootedValue, &RootedValue) -> Result<bool, String>
+{
+    use value;
+    let mut kept : Vec<RootedValue> = vec!();
+    'elements: for val in try!(list_elements(heap, list, "delete-duplicates")).into_iter() {
+        for seen in kept.iter() {
+            if try!(matches(heap, seen, &val)) {
+                continue 'elements;
+            }
+        }
+        kept.push(val);
+    }
+    Ok(Trampoline::Value(value::list(heap, kept.as_slice())))
+}
+
+fn delete_duplicates(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    match args.as_slice() {
+        [ref list]                => delete_duplicates_matching(heap, list, is_equal),
+        [ref list, ref procedure] => {
+            delete_duplicates_matching(heap, list, |heap, a, b| {
+                call_predicate(heap, procedure, vec!(a.clone(), b.clone()))
+            })
+        },
+        _                         => {
+            Err("Error: bad arguments to `delete-duplicates`".to_string())
+        },
+    }
+}
+
+fn iota(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    if args.len() == 0 || args.len() > 3 {
+        return Err("Error: bad arguments to `iota`".to_string());
+    }
+    let count = try!(index_arg(&args[0], "iota"));
+    let mut numbers = vec!();
+    for arg in args.slice_from(1).iter() {
+        numbers.push(try!(arg.to_integer().ok_or(
+            format!("Error: `iota` requires integers, got {}", **arg))));
+    }
+    let start = numbers.get(0).map(|n| *n).unwrap_or(0);
+    let step = numbers.get(1).map(|n| *n).unwrap_or(1);
+
+    let values : Vec<RootedValue> = range(0, count as i64)
+        .map(|i| Rooted::new(heap, Value::new_integer(start + i * step)))
+        .collect();
+    Ok(Trampoline::Value(value::list(heap, values.as_slice())))
+}
+
+fn find(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref pred, ref list] = args.as_slice() {
+        let found = try!(find_sublist(heap, list, "find", |heap, val| {
+            call_predicate(heap, pred, vec!(val.clone()))
+        }));
+        Ok(Trampoline::Value(match found {
+            Some(sublist) => sublist.car(heap).expect("Should be a pair"),
+            None          => Rooted::new(heap, Value::new_boolean(false)),
+        }))
+    } else {
+        Err("Error: bad arguments to `find`".to_string())
+    }
+}
+
+fn find_tail(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref pred, ref list] = args.as_slice() {
+        let found = try!(find_sublist(heap, list, "find-tail", |heap, val| {
+            call_predicate(heap, pred, vec!(val.clone()))
+        }));
+        Ok(Trampoline::Value(match found {
+            Some(sublist) => sublist,
+            None          => Rooted::new(heap, Value::new_boolean(false)),
+        }))
+    } else {
+        Err("Error: bad arguments to `find-tail`".to_string())
+    }
+}
+
+fn any(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let arguments = try!(list_arguments(heap, args.as_slice(), 1, "any"));
+    for call_args in arguments.into_iter() {
+        let result = try!(call_procedure(heap, &args[0], call_args));
+        if *result != Value::new_boolean(false) {
+            return Ok(Trampoline::Value(result));
+        }
+    }
+    Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(false))))
+}
+
+fn every(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let arguments = try!(list_arguments(heap, args.as_slice(), 1, "every"));
+    let mut result = Rooted::new(heap, Value::new_boolean(true));
+    for call_args in arguments.into_iter() {
+        result = try!(call_procedure(heap, &args[0], call_args));
+        if *result == Value::new_boolean(false) {
+            break;
+        }
+    }
+    Ok(Trampoline::Value(result))
+}
+
+fn count(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let arguments = try!(list_arguments(heap, args.as_slice(), 1, "count"));
+    let mut n = 0;
+    for call_args in arguments.into_iter() {
+        if try!(call_predicate(heap, &args[0], call_args)) {
+            n += 1;
+        }
+    }
+    Ok(Trampoline::Value(Rooted::new(heap, Value::new_integer(n))))
+}
+
+/// Split `list` into a vector of its first `k` elements and the rest of the
+/// list, for the primitive with the given name.
+fn split_list(heap: &mut Heap,
+              list: &RootedValue,
+              k: &RootedValue,
+              name: &str) -> Result<(Vec<RootedValue>, RootedValue), String> {
+    let k = try!(index_arg(k, name));
+    let mut prefix = Vec::with_capacity(k);
+    let mut rest = list.clone();
+    for _ in range(0, k) {
+        prefix.push(try!(rest.car(heap).ok_or(
+            format!("Error: index {} is out of bounds for `{}` of {}", k, name, **list))));
+        rest = rest.cdr(heap).expect("Should be a pair");
+    }
+    Ok((prefix, rest))
+}
+
+fn take(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    if let [ref list, ref k] = args.as_slice() {
+        let (prefix, _) = try!(split_list(heap, list, k, "take"));
+        Ok(Trampoline::Value(value::list(heap, prefix.as_slice())))
+    } else {
+        Err("Error: bad arguments to `take`".to_string())
+    }
+}
+
+fn drop(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref list, ref k] = args.as_slice() {
+        let (_, rest) = try!(split_list(heap, list, k, "drop"));
+        Ok(Trampoline::Value(rest))
+    } else {
+        Err("Error: bad arguments to `drop`".to_string())
+    }
+}
+
+/// `(split-at list k)`: split `list` into its first `k` elements and the rest.
+///
+/// Unlike SRFI-1, which returns the two parts as multiple values, this returns
+/// the list `(prefix rest)`, as with `partition`.
+fn split_at(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    if let [ref list, ref k] = args.as_slice() {
+        let (prefix, rest) = try!(split_list(heap, list, k, "split-at"));
+        let prefix = value::list(heap, prefix.as_slice());
+        Ok(Trampoline::Value(value::list(heap, &[prefix, rest])))
+    } else {
+        Err("Error: bad arguments to `split-at`".to_string())
+    }
+}
+
+fn last(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref list] = args.as_slice() {
+        let pair = try!(find_last_pair(heap, list, "last"));
+        Ok(Trampoline::Value(pair.car(heap).expect("Should be a pair")))
+    } else {
+        Err("Error: bad arguments to `last`".to_string())
+    }
+}
+
+fn append_map(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let results = try!(map_lists(heap, args.as_slice(), "append-map"));
+    append(heap, results)
+}
+
+fn filter_map(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    let results : Vec<RootedValue> = try!(map_lists(heap, args.as_slice(), "filter-map"))
+        .into_iter()
+        .filter(|val| **val != Value::new_boolean(false))
+        .collect();
+    Ok(Trampoline::Value(value::list(heap, results.as_slice())))
+}
+
+/// Get the hash table argument of the primitive with the given name.
+fn hash_table_arg(heap: &mut Heap,
+                  arg: &RootedValue,
+                  name: &str) -> Result<RootedHashTablePtr, String> {
+    arg.to_hash_table(heap).ok_or(
+        format!("Error: `{}` requires a hash table, got {}", name, **arg))
+}
+
+fn make_hash_table(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let equivalence = match args.as_slice() {
+        []                => Equivalence::Equal,
+        [ref equivalence] => match **equivalence {
+            Value::Primitive(p) if p.name() == "eq?"    => Equivalence::Eq,
+            Value::Primitive(p) if p.name() == "eqv?"   => Equivalence::Eqv,
+            Value::Primitive(p) if p.name() == "equal?" => Equivalence::Equal,
+            _                                           => {
+                return Err(format!("Error: `make-hash-table` requires `eq?`, `eqv?` \
+                                    or `equal?`, got {}", **equivalence));
+            },
+        },
+        _                 => return Err("Error: bad arguments to `make-hash-table`".to_string()),
+    };
+    Ok(Trampoline::Value(Value::new_hash_table(heap, HashTable::new(equivalence))))
+}
+
+/// Make a new hash table with the given equivalence, for the primitive with the
+/// given name.
+fn make_hash_table_with(heap: &mut Heap,
+                        args: Vec<RootedValue>,
+                        name: &str,
+                        equivalence: Equivalence) -> TrampolineResult {
+    if args.len() != 0 {
+        return Err(format!("Error: bad arguments to `{}`", name));
+    }
+    Ok(Trampoline::Value(Value::new_hash_table(heap, HashTable::new(equivalence))))
+}
+
+fn make_eq_hash_table(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    make_hash_table_with(heap, args, "make-eq-hash-table", Equivalence::Eq)
+}
+
+fn make_eqv_hash_table(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    make_hash_table_with(heap, args, "make-eqv-hash-table", Equivalence::Eqv)
+}
+
+fn make_equal_hash_table(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    make_hash_table_with(heap, args, "make-equal-hash-table", Equivalence::Equal)
+}
+
+fn make_string_hash_table(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    make_hash_table_with(heap, args, "make-string-hash-table", Equivalence::String)
+}
+
+fn hash_table_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::HashTable(_) => true,
+            _                   => false,
+        }))))
+    } else {
+        Err("Error: bad arguments to `hash-table?`".to_string())
+    }
+}
+
+/// Look up `key` in `table`, calling `fail` if it is given and there is no entry
+/// for the key, for the primitive with the given name.
+fn hash_table_lookup(heap: &mut Heap,
+                     table: &RootedValue,
+                     key: &RootedValue,
+                     fail: Option<&RootedValue>,
+                     name: &str) -> SchemeResult {
+    let table = try!(hash_table_arg(heap, table, name));
+    match (table.get(&**key), fail) {
+        (Some(val), _)     => Ok(Rooted::new(heap, val)),
+        (None, Some(fail)) => call_procedure(heap, fail, vec!()),
+        (None, None)       => Err(format!("Error: no entry in `{}` for key {}", name, **key)),
+    }
+}
+
+fn hash_table_ref(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let result = match args.as_slice() {
+        [ref table, ref key]           => {
+            hash_table_lookup(heap, table, key, None, "hash-table-ref")
+        },
+        [ref table, ref key, ref fail] => {
+            hash_table_lookup(heap, table, key, Some(fail), "hash-table-ref")
+        },
+        _                              => {
+            return Err("Error: bad arguments to `hash-table-ref`".to_string());
+        },
+    };
+    result.map(|v| Trampoline::Value(v))
+}
+
+fn hash_table_ref_default(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref table, ref key, ref default] = args.as_slice() {
+        let table = try!(hash_table_arg(heap, table, "hash-table-ref/default"));
+        Ok(Trampoline::Value(match table.get(&**key) {
+            Some(val) => Rooted::new(heap, val),
+            None      => default.clone(),
+        }))
+    } else {
+        Err("Error: bad arguments to `hash-table-ref/default`".to_string())
+    }
+}
+
+fn hash_table_set_bang(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref table, ref key, ref val] = args.as_slice() {
+        let mut table = try!(hash_table_arg(heap, table, "hash-table-set!"));
+        try!(table.insert(**key, **val));
+        Ok(Trampoline::Value(heap.unspecified_symbol()))
+    } else {
+        Err("Error: bad arguments to `hash-table-set!`".to_string())
+    }
+}
+
+fn hash_table_delete_bang(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref table, ref key] = args.as_slice() {
+        let mut table = try!(hash_table_arg(heap, table, "hash-table-delete!"));
+        table.remove(&**key);
+        Ok(Trampoline::Value(heap.unspecified_symbol()))
+    } else {
+        Err("Error: bad arguments to `hash-table-delete!`".to_string())
+    }
+}
+
+fn hash_table_contains_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref table, ref key] = args.as_slice() {
+        let table = try!(hash_table_arg(heap, table, "hash-table-contains?"));
+        let contains = table.get(&**key).is_some();
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(contains))))
+    } else {
+        Err("Error: bad arguments to `hash-table-contains?`".to_string())
+    }
+}
+
+/// Replace the value for `key` in `table` with the result of calling `proc` on
+/// it, for `hash-table-update!` and `hash-table-update!/default`.
+fn hash_table_update(heap: &mut Heap,
+                     table: &RootedValue,
+                     key: &RootedValue,
+                     procedure: &RootedValue,
+                     current: RootedValue,
+                     name: &str) -> TrampolineResult {
+    let val = try!(call_procedure(heap, procedure, vec!(current)));
+    let mut table = try!(hash_table_arg(heap, table, name));
+    try!(table.insert(**key, *val));
+    Ok(Trampoline::Value(heap.unspecified_symbol()))
+}
+
+fn hash_table_update_bang(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let name = "hash-table-update!";
+    match args.as_slice() {
+        [ref table, ref key, ref procedure]           => {
+            let current = try!(hash_table_lookup(heap, table, key, None, name));
+            hash_table_update(heap, table, key, procedure, current, name)
+        },
+        [ref table, ref key, ref procedure, ref fail] => {
+            let current = try!(hash_table_lookup(heap, table, key, Some(fail), name));
+            hash_table_update(heap, table, key, procedure, current, name)
+        },
+        _                                             => {
+            Err("Error: bad arguments to `hash-table-update!`".to_string())
+        },
+    }
+}
+
+fn hash_table_update_bang_default(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let name = "hash-table-update!/default";
+    if let [ref table, ref key, ref procedure, ref default] = args.as_slice() {
+        let current = {
+            let table = try!(hash_table_arg(heap, table, name));
+            match table.get(&**key) {
+                Some(val) => Rooted::new(heap, val),
+                None      => default.clone(),
+            }
+        };
+        hash_table_update(heap, table, key, procedure, current, name)
+    } else {
+        Err("Error: bad arguments to `hash-table-update!/default`".to_string())
+    }
+}
+
+fn hash_table_count(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref table] = args.as_slice() {
+        let table = try!(hash_table_arg(heap, table, "hash-table-count"));
+        let len = table.len();
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_integer(len as i64))))
+    } else {
+        Err("Error: bad arguments to `hash-table-count`".to_string())
+    }
+}
+
+/// Get the rooted entries of the given hash table argument, for the primitive
+/// with the given name.
+fn hash_table_entries(heap: &mut Heap,
+                      table: &RootedValue,
+                      name: &str) -> Result<Vec<(RootedValue, RootedValue)>, String> {
+    let table = try!(hash_table_arg(heap, table, name));
+    Ok(table.entries().into_iter()
+       .map(|(key, val)| (Rooted::new(heap, key), Rooted::new(heap, val)))
+       .collect())
+}
+
+fn hash_table_keys(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    if let [ref table] = args.as_slice() {
+        let keys : Vec<RootedValue> = try!(hash_table_entries(heap, table, "hash-table-keys"))
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        Ok(Trampoline::Value(value::list(heap, keys.as_slice())))
+    } else {
+        Err("Error: bad arguments to `hash-table-keys`".to_string())
+    }
+}
+
+fn hash_table_values(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    if let [ref table] = args.as_slice() {
+        let vals : Vec<RootedValue> = try!(hash_table_entries(heap, table, "hash-table-values"))
+            .into_iter()
+            .map(|(_, val)| val)
+            .collect();
+        Ok(Trampoline::Value(value::list(heap, vals.as_slice())))
+    } else {
+        Err("Error: bad arguments to `hash-table-values`".to_string())
+    }
+}
+
+fn hash_table_to_alist(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+    if let [ref table] = args.as_slice() {
+        let entries = try!(hash_table_entries(heap, table, "hash-table->alist"));
+        let pairs : Vec<RootedValue> = entries.iter()
+            .map(|&(ref key, ref val)| Value::new_pair(heap, key, val))
+            .collect();
+        Ok(Trampoline::Value(value::list(heap, pairs.as_slice())))
+    } else {
+        Err("Error: bad arguments to `hash-table->alist`".to_string())
+    }
+}
+
+fn hash_table_walk(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref table, ref procedure] = args.as_slice() {
+        // Walk a snapshot of the entries, so that the procedure may modify the
+        // table as it goes.
+        for (key, val) in try!(hash_table_entries(heap, table, "hash-table-walk")).into_iter() {
+            try!(call_procedure(heap, procedure, vec!(key, val)));
+        }
+        Ok(Trampoline::Value(heap.unspecified_symbol()))
+    } else {
+        Err("Error: bad arguments to `hash-table-walk`".to_string())
+    }
+}
+
+// The record primitives aren't bound in the global environment. Instead,
+// `define-record-type` expands into procedures that call them, passing along
+// the record type and, for accessors and modifiers, the field index and the
+// name of the procedure to report in errors.
+
+/// Get the record type argument of the record primitive with the given name.
+fn record_type_arg(heap: &mut Heap,
+                   arg: &RootedValue,
+                   name: &str) -> Result<RootedRecordPtr, String> {
+    arg.to_record(heap)
+        .and_then(|record| if record.is_type() { Some(record) } else { None })
+        .ok_or(format!("Error: `{}` requires a record type, got {}", name, **arg))
+}
+
+/// Get the argument of the accessor or modifier with the given name, which must
+/// be an instance of the given record type.
+fn record_instance_arg(heap: &mut Heap,
+                       arg: &RootedValue,
+                       record_type: &RootedRecordPtr,
+                       name: &RootedValue) -> Result<RootedRecordPtr, String> {
+    arg.to_record(heap)
+        .and_then(|record| {
+            if record.record_type() == Some(**record_type) { Some(record) } else { None }
+        })
+        .ok_or(format!("Error: `{}` requires a {} record, got {}",
+                       **name, record_type.type_name(), **arg))
+}
+
+/// `(make-record type field-value ...)`: make a new instance of the record type,
+/// with a value for each of its fields.
+pub fn make_record(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() == 0 {
+        return Err("Error: bad arguments to `make-record`".to_string());
+    }
+    let record_type = try!(record_type_arg(heap, &args[0], "make-record"));
+    let fields : Vec<Value> = args.slice_from(1).iter().map(|val| **val).collect();
+    if fields.len() != record_type.field_names().len() {
+        return Err("Error: bad arguments to `make-record`".to_string());
+    }
+    Ok(Trampoline::Value(Value::new_record(heap, Record::new_instance(*record_type, fields))))
+}
+
+/// `(record? type obj)`: return true if `obj` is an instance of the record
+/// type.
+pub fn record_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref record_type, ref obj] = args.as_slice() {
+        let record_type = try!(record_type_arg(heap, record_type, "record?"));
+        let is_instance = obj.to_record(heap)
+            .map_or(false, |record| record.record_type() == Some(*record_type));
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(is_instance))))
+    } else {
+        Err("Error: bad arguments to `record?`".to_string())
+    }
+}
+
+/// `(record-ref type index name record)`: get the value of the field at `index`
+/// of `record`, which must be an instance of the record type.
+pub fn record_ref(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref record_type, ref index, ref name, ref record] = args.as_slice() {
+        let record_type = try!(record_type_arg(heap, record_type, "record-ref"));
+        let i = try!(index_arg(index, "record-ref"));
+        let record = try!(record_instance_arg(heap, record, &record_type, name));
+        let val = try!(record.field(i).ok_or(
+            "Error: bad arguments to `record-ref`".to_string()));
+        Ok(Trampoline::Value(Rooted::new(heap, val)))
+    } else {
+        Err("Error: bad arguments to `record-ref`".to_string())
+    }
+}
+
+/// `(record-set! type index name record val)`: set the value of the field at
+/// `index` of `record`, which must be an instance of the record type.
+pub fn record_set_bang(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref record_type, ref index, ref name, ref record, ref val] = args.as_slice() {
+        let record_type = try!(record_type_arg(heap, record_type, "record-set!"));
+        let i = try!(index_arg(index, "record-set!"));
+        let mut record = try!(record_instance_arg(heap, record, &record_type, name));
+        if i >= record.fields().len() {
+            return Err("Error: bad arguments to `record-set!`".to_string());
+        }
+        record.set_field(i, **val);
+        Ok(Trampoline::Value(heap.unspecified_symbol()))
+    } else {
+        Err("Error: bad arguments to `record-set!`".to_string())
+    }
+}
+
 fn apply(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
     // Note: we don't support concatenating many argument lists yet:
     //
@@ -106,218 +1130,1298 @@ fn apply(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
             .collect());
         apply_invocation(heap, proc_val, v)
     } else {
-        Err("Error: bad arguments to `apply`".to_string())
+        Err("Error: bad arguments to `apply`".to_string())
+    }
+}
+
+// Conditions are raised by calling the innermost exception handler installed
+// with `with-exception-handler`, or by unwinding to the innermost `guard`. The
+// conditions raised by the interpreter itself are error objects: a record with
+// a kind, which `file-error?` and `read-error?` tell apart, a message, and a
+// list of irritants.
+
+/// Make a new error object of the given kind.
+fn new_error_object(heap: &mut Heap,
+                    kind: &str,
+                    message: &str,
+                    irritants: &[RootedValue]) -> RootedValue {
+    let record_type = heap.error_object_type();
+    let kind = heap.get_or_create_symbol(kind.to_string());
+    let message = Value::new_string(heap, message.to_string());
+    let irritants = value::list(heap, irritants);
+    let fields = vec!(*kind, *message, *irritants);
+    Value::new_record(heap, Record::new_instance(*record_type, fields))
+}
+
+/// Raise the given condition. If the innermost handler is a procedure, call it
+/// with the condition in the dynamic environment of the raise, except with the
+/// outer handlers installed. If it is a `guard`, or there are no handlers,
+/// unwind with the given error message instead.
+///
+/// When `continuable` is true, the handler's result is returned. Otherwise, a
+/// handler that returns raises a secondary exception in the same dynamic
+/// environment as the handler, so that this always returns an error.
+fn raise_condition(heap: &mut Heap,
+                   condition: &RootedValue,
+                   message: String,
+                   continuable: bool) -> SchemeResult {
+    match heap.pop_handler() {
+        Some(Handler::Procedure(handler)) => {
+            let mut result = call_procedure(heap, &handler, vec!(condition.clone()));
+            if result.is_ok() && !continuable {
+                let secondary_message =
+                    "Error: exception handler returned from non-continuable `raise`";
+                let secondary = new_error_object(heap, "error", secondary_message,
+                                                 &[condition.clone()]);
+                result = raise_condition(heap, &secondary,
+                                         format!("{}\n{}", message, secondary_message),
+                                         false);
+            }
+            heap.push_handler(Handler::Procedure(handler));
+            result
+        },
+        Some(Handler::Guard)              => {
+            heap.push_handler(Handler::Guard);
+            heap.raise(condition);
+            Err(message)
+        },
+        None                              => Err(message),
+    }
+}
+
+/// Raise the given condition non-continuably, and return the error for the
+/// caller to unwind with.
+fn raise_non_continuable(heap: &mut Heap,
+                         condition: &RootedValue,
+                         message: String) -> String {
+    raise_condition(heap, condition, message, false)
+        .err()
+        .expect("A non-continuable raise should always unwind")
+}
+
+/// Raise a new error object of the given kind non-continuably, and return the
+/// error for the caller to unwind with.
+fn raise_error(heap: &mut Heap,
+               kind: &str,
+               message: String,
+               irritants: &[RootedValue]) -> String {
+    let condition = new_error_object(heap, kind, message.as_slice(), irritants);
+    raise_non_continuable(heap, &condition, message)
+}
+
+/// `(error message irritant ...)`: raise a new error object with the given
+/// message and irritants.
+fn error(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let mut string = String::from_str("ERROR!");
+    for val in args.iter() {
+        string.push_str(format!("\n\t{}", **val).as_slice());
+    }
+
+    let message = match args.first() {
+        Some(val) => match val.to_string_ptr(heap) {
+            Some(str) => str.as_slice().to_string(),
+            None      => format!("{}", **val),
+        },
+        None      => String::new(),
+    };
+    let irritants = if args.len() > 0 { args.slice_from(1) } else { args.as_slice() };
+    let condition = new_error_object(heap, "error", message.as_slice(), irritants);
+    Err(raise_non_continuable(heap, &condition, string))
+}
+
+fn raise(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref obj] = args.as_slice() {
+        let message = format!("Error: uncaught exception: {}", **obj);
+        Err(raise_non_continuable(heap, obj, message))
+    } else {
+        Err("Error: bad arguments to `raise`".to_string())
+    }
+}
+
+pub fn raise_continuable(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref obj] = args.as_slice() {
+        let message = format!("Error: uncaught exception: {}", **obj);
+        Ok(Trampoline::Value(try!(raise_condition(heap, obj, message, true))))
+    } else {
+        Err("Error: bad arguments to `raise-continuable`".to_string())
+    }
+}
+
+/// `(with-exception-handler handler thunk)`: call `thunk` with `handler`
+/// installed as the innermost exception handler.
+///
+/// Only conditions raised with `raise`, `raise-continuable` and `error`, and
+/// file and read errors, are passed to handlers. Other errors, such as bad
+/// arguments to a primitive, unwind straight to the innermost `guard`.
+fn with_exception_handler(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref handler, ref thunk] = args.as_slice() {
+        heap.push_handler(Handler::Procedure(handler.clone()));
+        let result = call_procedure(heap, thunk, vec!());
+        heap.pop_handler();
+        Ok(Trampoline::Value(try!(result)))
+    } else {
+        Err("Error: bad arguments to `with-exception-handler`".to_string())
+    }
+}
+
+// The `guard` primitives aren't bound in the global environment. Instead, the
+// `guard` special form expands into calls to them.
+
+/// `(guard thunk handler)`: call `thunk` with a `guard` installed as the
+/// innermost exception handler. If a condition is raised to the guard, call
+/// `handler` with it once `thunk` has unwound, and return its result.
+///
+/// Errors that weren't raised as conditions, such as bad arguments to a
+/// primitive, are also caught, as error objects with the error's message.
+/// Calls to `exit` are never caught.
+pub fn guard(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref thunk, ref handler] = args.as_slice() {
+        // Forget anything left behind by an error that nothing caught.
+        heap.take_raised();
+        heap.take_error_message();
+
+        heap.push_handler(Handler::Guard);
+        let result = call_procedure(heap, thunk, vec!());
+        heap.pop_handler();
+
+        match result {
+            Ok(val) => Ok(Trampoline::Value(val)),
+            Err(e)  => {
+                if heap.exit_status().is_some() {
+                    return Err(e);
+                }
+                let message = heap.take_error_message().unwrap_or(e);
+                let condition = match heap.take_raised() {
+                    Some(condition) => condition,
+                    None            => new_error_object(heap, "error", message.as_slice(), &[]),
+                };
+                apply_invocation(heap, handler, vec!(condition))
+            },
+        }
+    } else {
+        Err("Error: bad arguments to `guard`".to_string())
+    }
+}
+
+/// `(guard-clause test receiver otherwise)`: if `test` is true, return the
+/// result of calling `receiver` with it, or `test` itself if `receiver` is
+/// `#f`. Otherwise, return the result of calling the thunk `otherwise`.
+pub fn guard_clause(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref test, ref receiver, ref otherwise] = args.as_slice() {
+        if **test == Value::new_boolean(false) {
+            apply_invocation(heap, otherwise, vec!())
+        } else if **receiver == Value::new_boolean(false) {
+            Ok(Trampoline::Value(test.clone()))
+        } else {
+            apply_invocation(heap, receiver, vec!(test.clone()))
+        }
+    } else {
+        Err("Error: bad arguments to `guard-clause`".to_string())
+    }
+}
+
+/// Get the error object argument of the primitive with the given name.
+fn error_object_arg(heap: &mut Heap,
+                    arg: &RootedValue,
+                    name: &str) -> Result<RootedRecordPtr, String> {
+    let record_type = heap.error_object_type();
+    arg.to_record(heap)
+        .and_then(|record| {
+            if record.record_type() == Some(*record_type) { Some(record) } else { None }
+        })
+        .ok_or(format!("Error: cannot use `{}` with non-error object {}", name, **arg))
+}
+
+/// Return true if the given value is an error object of the given kind, or of
+/// any kind if `kind` is `None`.
+fn is_error_object(heap: &mut Heap, val: &RootedValue, kind: Option<&str>) -> bool {
+    match error_object_arg(heap, val, "error-object?") {
+        Ok(record) => match kind {
+            Some(kind) => {
+                let kind = heap.get_or_create_symbol(kind.to_string());
+                record.field(0) == Some(*kind)
+            },
+            None       => true,
+        },
+        Err(_)     => false,
+    }
+}
+
+fn error_object_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let is_error_object = is_error_object(heap, arg, None);
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(is_error_object))))
+    } else {
+        Err("Error: bad arguments to `error-object?`".to_string())
+    }
+}
+
+fn file_error_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let is_file_error = is_error_object(heap, arg, Some("file"));
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(is_file_error))))
+    } else {
+        Err("Error: bad arguments to `file-error?`".to_string())
+    }
+}
+
+fn read_error_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let is_read_error = is_error_object(heap, arg, Some("read"));
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(is_read_error))))
+    } else {
+        Err("Error: bad arguments to `read-error?`".to_string())
+    }
+}
+
+fn error_object_message(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let record = try!(error_object_arg(heap, arg, "error-object-message"));
+        let message = record.field(1).expect("Error objects should have a message");
+        Ok(Trampoline::Value(Rooted::new(heap, message)))
+    } else {
+        Err("Error: bad arguments to `error-object-message`".to_string())
+    }
+}
+
+fn error_object_irritants(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let record = try!(error_object_arg(heap, arg, "error-object-irritants"));
+        let irritants = record.field(2).expect("Error objects should have irritants");
+        Ok(Trampoline::Value(Rooted::new(heap, irritants)))
+    } else {
+        Err("Error: bad arguments to `error-object-irritants`".to_string())
+    }
+}
+
+fn print(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    for val in args.iter() {
+        println!("{}", **val);
+    }
+    Ok(Trampoline::Value(heap.unspecified_symbol()))
+}
+
+/// Get the port from the given optional port argument of the primitive with the
+/// given name, defaulting to the current input port.
+fn input_port_arg(heap: &mut Heap,
+                  args: &[RootedValue],
+                  name: &str) -> Result<RootedPortPtr, String> {
+    match args {
+        []         => Ok(heap.current_input_port()),
+        [ref port] => port.to_port(heap).ok_or(
+            format!("Error: cannot use `{}` with non-port {}", name, **port)),
+        _          => Err(format!("Error: bad arguments to `{}`", name)),
+    }
+}
+
+/// Get the port from the given optional port argument of the primitive with the
+/// given name, defaulting to the current output port.
+fn output_port_arg(heap: &mut Heap,
+                   args: &[RootedValue],
+                   name: &str) -> Result<RootedPortPtr, String> {
+    match args {
+        []         => Ok(heap.current_output_port()),
+        [ref port] => port.to_port(heap).ok_or(
+            format!("Error: cannot use `{}` with non-port {}", name, **port)),
+        _          => Err(format!("Error: bad arguments to `{}`", name)),
+    }
+}
+
+fn read(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let mut port = try!(input_port_arg(heap, args.as_slice(), "read"));
+    let name = port.name().to_string();
+    let mut reader = Read::new(PortReader::new(*port), heap, name);
+    let result = match reader.next() {
+        Some((_, read_result)) => try!(read_result.map_err(|e| {
+            raise_error(heap, "read", e, &[])
+        })),
+        None                   => Rooted::new(heap, Value::EofObject),
+    };
+
+    // The reader peeks ahead of the end of the datum, so give any characters
+    // it peeked at back to the port.
+    for c in reader.unread_chars().into_iter().rev() {
+        port.unread_char(c);
+    }
+
+    Ok(Trampoline::Value(result))
+}
+
+/// Get the path from the given argument of the primitive with the given name.
+fn path_arg(heap: &mut Heap, arg: &RootedValue, name: &str) -> Result<String, String> {
+    arg.to_string_ptr(heap)
+        .map(|str| str.as_slice().to_string())
+        .ok_or(format!("Error: cannot use `{}` with non-string path {}", name, **arg))
+}
+
+/// Open a file port for the `open-*-file` primitive with the given name.
+fn open_file(heap: &mut Heap,
+             args: Vec<RootedValue>,
+             name: &str,
+             open: fn(&str) -> PortResult<Port>,
+             binary: bool) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let path = try!(path_arg(heap, arg, name));
+        let port = try!(open(path.as_slice()).map_err(|e| {
+            raise_error(heap, "file", e, &[arg.clone()])
+        }));
+        let port = if binary { port.binary() } else { port };
+        Ok(Trampoline::Value(Value::new_port(heap, port)))
+    } else {
+        Err(format!("Error: bad arguments to `{}`", name))
+    }
+}
+
+fn open_input_file(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    open_file(heap, args, "open-input-file", Port::open_input_file, false)
+}
+
+fn open_binary_input_file(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    open_file(heap, args, "open-binary-input-file", Port::open_input_file, true)
+}
+
+fn open_output_file(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    open_file(heap, args, "open-output-file", Port::open_output_file, false)
+}
+
+fn open_binary_output_file(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    open_file(heap, args, "open-binary-output-file", Port::open_output_file, true)
+}
+
+/// Open a file port, call the given procedure with it, and close the port once
+/// the procedure returns or fails.
+fn call_with_file(heap: &mut Heap,
+                  args: Vec<RootedValue>,
+                  name: &str,
+                  open: fn(&str) -> PortResult<Port>) -> TrampolineResult {
+    if let [ref path_val, ref procedure] = args.as_slice() {
+        let path = try!(path_arg(heap, path_val, name));
+        let port = try!(open(path.as_slice()).map_err(|e| {
+            raise_error(heap, "file", e, &[path_val.clone()])
+        }));
+        let port = Value::new_port(heap, port);
+        let result = apply_invocation(heap, procedure, vec!(port.clone()))
+            .and_then(|trampoline| trampoline.run(heap));
+        try!(port.to_port(heap).expect("Should be a port").close());
+        Ok(Trampoline::Value(try!(result)))
+    } else {
+        Err(format!("Error: bad arguments to `{}`", name))
+    }
+}
+
+fn call_with_input_file(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    call_with_file(heap, args, "call-with-input-file", Port::open_input_file)
+}
+
+fn call_with_output_file(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    call_with_file(heap, args, "call-with-output-file", Port::open_output_file)
+}
+
+/// Call the given thunk with the given port as the current output port,
+/// restoring the previous current output port once the thunk returns or fails.
+fn with_output_to_port(heap: &mut Heap,
+                       port: &RootedPortPtr,
+                       thunk: &RootedValue) -> SchemeResult {
+    let previous = heap.set_current_output_port(port);
+    let result = apply_invocation(heap, thunk, vec!())
+        .and_then(|trampoline| trampoline.run(heap));
+    heap.set_current_output_port(&previous);
+    result
+}
+
+fn with_output_to_file(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref path_val, ref thunk] = args.as_slice() {
+        let path = try!(path_arg(heap, path_val, "with-output-to-file"));
+        let port = try!(Port::open_output_file(path.as_slice()).map_err(|e| {
+            raise_error(heap, "file", e, &[path_val.clone()])
+        }));
+        let mut port = heap.allocate_port(port);
+        let result = with_output_to_port(heap, &port, thunk);
+        try!(port.close());
+        Ok(Trampoline::Value(try!(result)))
+    } else {
+        Err("Error: bad arguments to `with-output-to-file`".to_string())
+    }
+}
+
+fn open_input_string(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let str = try!(arg.to_string_ptr(heap).ok_or(
+            "Error: cannot use `open-input-string` with non-strings".to_string()));
+        let port = Port::open_input_string(str.as_slice().to_string());
+        Ok(Trampoline::Value(Value::new_port(heap, port)))
+    } else {
+        Err("Error: bad arguments to `open-input-string`".to_string())
+    }
+}
+
+fn open_output_string(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() != 0 {
+        return Err("Error: bad arguments to `open-output-string`".to_string());
+    }
+    Ok(Trampoline::Value(Value::new_port(heap, Port::open_output_string())))
+}
+
+fn get_output_string(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let port = try!(arg.to_port(heap).ok_or(
+            format!("Error: cannot use `get-output-string` with non-port {}", **arg)));
+        let str = try!(port.output_string());
+        Ok(Trampoline::Value(Value::new_string(heap, str)))
+    } else {
+        Err("Error: bad arguments to `get-output-string`".to_string())
+    }
+}
+
+fn with_output_to_string(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref thunk] = args.as_slice() {
+        let port = heap.allocate_port(Port::open_output_string());
+        try!(with_output_to_port(heap, &port, thunk));
+        let str = try!(port.output_string());
+        Ok(Trampoline::Value(Value::new_string(heap, str)))
+    } else {
+        Err("Error: bad arguments to `with-output-to-string`".to_string())
+    }
+}
+
+fn file_exists_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let path = try!(path_arg(heap, arg, "file-exists?"));
+        let exists = Path::new(path.as_slice()).exists();
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(exists))))
+    } else {
+        Err("Error: bad arguments to `file-exists?`".to_string())
+    }
+}
+
+fn delete_file(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use std::old_io::fs;
+
+    if let [ref arg] = args.as_slice() {
+        let path = try!(path_arg(heap, arg, "delete-file"));
+        try!(fs::unlink(&Path::new(path.as_slice())).map_err(|e| {
+            let message = format!("Error: could not delete file {}: {}", path, e);
+            raise_error(heap, "file", message, &[arg.clone()])
+        }));
+        Ok(Trampoline::Value(heap.unspecified_symbol()))
+    } else {
+        Err("Error: bad arguments to `delete-file`".to_string())
+    }
+}
+
+fn read_u8(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let mut port = try!(input_port_arg(heap, args.as_slice(), "read-u8"));
+    Ok(Trampoline::Value(match try!(port.read_u8()) {
+        Some(b) => Rooted::new(heap, Value::new_integer(b as i64)),
+        None    => Rooted::new(heap, Value::EofObject),
+    }))
+}
+
+fn peek_u8(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let mut port = try!(input_port_arg(heap, args.as_slice(), "peek-u8"));
+    Ok(Trampoline::Value(match try!(port.peek_u8()) {
+        Some(b) => Rooted::new(heap, Value::new_integer(b as i64)),
+        None    => Rooted::new(heap, Value::EofObject),
+    }))
+}
+
+fn write_u8(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() == 0 {
+        return Err("Error: bad arguments to `write-u8`".to_string());
+    }
+    let b = try!(args[0].to_integer().ok_or(
+        "Error: cannot use `write-u8` with non-integers".to_string()));
+    if b < 0 || b > 255 {
+        return Err(format!("Error: not a byte: {}", b));
+    }
+    let mut port = try!(output_port_arg(heap, args.slice_from(1), "write-u8"));
+    try!(port.write_u8(b as u8));
+    Ok(Trampoline::Value(heap.unspecified_symbol()))
+}
+
+fn binary_port_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Port(p) => p.is_binary(),
+            _              => false,
+        }))))
+    } else {
+        Err("Error: bad arguments to `binary-port?`".to_string())
+    }
+}
+
+fn textual_port_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Port(p) => !p.is_binary(),
+            _              => false,
+        }))))
+    } else {
+        Err("Error: bad arguments to `textual-port?`".to_string())
     }
 }
 
-fn error(_: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    let mut string = String::from_str("ERROR!");
-    for val in args.iter() {
-        string.push_str(format!("\n\t{}", **val).as_slice());
+/// Print the first argument in the given style to the optional port argument,
+/// or the current output port.
+fn print_to_port(heap: &mut Heap,
+                 args: Vec<RootedValue>,
+                 name: &str,
+                 style: PrintStyle) -> TrampolineResult {
+    if args.len() == 0 {
+        return Err(format!("Error: bad arguments to `{}`", name));
     }
-    Err(string)
+    let mut port = try!(output_port_arg(heap, args.slice_from(1), name));
+    try!(port.write_str(format!("{}", args[0].printed(style)).as_slice()));
+    Ok(Trampoline::Value(heap.unspecified_symbol()))
 }
 
-fn print(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    for val in args.iter() {
-        println!("{}", **val);
+fn write(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    print_to_port(heap, args, "write", PrintStyle::Write)
+}
+
+fn write_shared(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    print_to_port(heap, args, "write-shared", PrintStyle::WriteShared)
+}
+
+fn write_simple(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    print_to_port(heap, args, "write-simple", PrintStyle::WriteSimple)
+}
+
+fn display(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    print_to_port(heap, args, "display", PrintStyle::Display)
+}
+
+fn pretty_print(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() == 0 {
+        return Err("Error: bad arguments to `pretty-print`".to_string());
     }
+    let mut port = try!(output_port_arg(heap, args.slice_from(1), "pretty-print"));
+    let printed = pretty::pretty_print(&*args[0], pretty::DEFAULT_WIDTH);
+    try!(port.write_str(printed.as_slice()));
+    try!(port.write_char('\n'));
     Ok(Trampoline::Value(heap.unspecified_symbol()))
 }
 
-fn read(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    // Only supports reading from stdin right now.
+fn current_input_port(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() != 0 {
+        return Err("Error: bad arguments to `current-input-port`".to_string());
+    }
+    let port = heap.current_input_port();
+    Ok(Trampoline::Value(Rooted::new(heap, Value::Port(*port))))
+}
+
+fn current_output_port(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() != 0 {
+        return Err("Error: bad arguments to `current-output-port`".to_string());
+    }
+    let port = heap.current_output_port();
+    Ok(Trampoline::Value(Rooted::new(heap, Value::Port(*port))))
+}
+
+fn current_error_port(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() != 0 {
+        return Err("Error: bad arguments to `current-error-port`".to_string());
+    }
+    let port = heap.current_error_port();
+    Ok(Trampoline::Value(Rooted::new(heap, Value::Port(*port))))
+}
+
+fn read_char(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let mut port = try!(input_port_arg(heap, args.as_slice(), "read-char"));
+    Ok(Trampoline::Value(match try!(port.read_char()) {
+        Some(c) => Rooted::new(heap, Value::new_character(c)),
+        None    => Rooted::new(heap, Value::EofObject),
+    }))
+}
+
+fn peek_char(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let mut port = try!(input_port_arg(heap, args.as_slice(), "peek-char"));
+    Ok(Trampoline::Value(match try!(port.peek_char()) {
+        Some(c) => Rooted::new(heap, Value::new_character(c)),
+        None    => Rooted::new(heap, Value::EofObject),
+    }))
+}
+
+fn read_line(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let mut port = try!(input_port_arg(heap, args.as_slice(), "read-line"));
+    Ok(Trampoline::Value(match try!(port.read_line()) {
+        Some(line) => Value::new_string(heap, line),
+        None       => Rooted::new(heap, Value::EofObject),
+    }))
+}
+
+fn read_string(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() == 0 {
+        return Err("Error: bad arguments to `read-string`".to_string());
+    }
+    let k = try!(args[0].to_integer().ok_or(
+        "Error: cannot use `read-string` with a non-integer count".to_string()));
+    if k < 0 {
+        return Err(format!("Error: cannot read a negative number of characters: {}", k));
+    }
+    let mut port = try!(input_port_arg(heap, args.slice_from(1), "read-string"));
+    Ok(Trampoline::Value(match try!(port.read_string(k as usize)) {
+        Some(str) => Value::new_string(heap, str),
+        None      => Rooted::new(heap, Value::EofObject),
+    }))
+}
+
+fn write_char(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() == 0 {
+        return Err("Error: bad arguments to `write-char`".to_string());
+    }
+    let c = try!(args[0].to_character().ok_or(
+        "Error: cannot use `write-char` with non-characters".to_string()));
+    let mut port = try!(output_port_arg(heap, args.slice_from(1), "write-char"));
+    try!(port.write_char(c));
+    Ok(Trampoline::Value(heap.unspecified_symbol()))
+}
+
+fn write_string(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() == 0 {
+        return Err("Error: bad arguments to `write-string`".to_string());
+    }
+    let str = try!(args[0].to_string_ptr(heap).ok_or(
+        "Error: cannot use `write-string` with non-strings".to_string()));
+    let mut port = try!(output_port_arg(heap, args.slice_from(1), "write-string"));
+    try!(port.write_str(str.as_slice()));
+    Ok(Trampoline::Value(heap.unspecified_symbol()))
+}
+
+fn newline(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let mut port = try!(output_port_arg(heap, args.as_slice(), "newline"));
+    try!(port.write_char('\n'));
+    Ok(Trampoline::Value(heap.unspecified_symbol()))
+}
+
+fn close_port(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let mut port = try!(arg.to_port(heap).ok_or(
+            format!("Error: cannot use `close-port` with non-port {}", **arg)));
+        try!(port.close());
+        Ok(Trampoline::Value(heap.unspecified_symbol()))
+    } else {
+        Err("Error: bad arguments to `close-port`".to_string())
+    }
+}
+
+fn eof_object(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if args.len() != 0 {
+        return Err("Error: bad arguments to `eof-object`".to_string());
+    }
+    Ok(Trampoline::Value(Rooted::new(heap, Value::EofObject)))
+}
+
+fn eof_object_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(**arg == Value::EofObject))))
+    } else {
+        Err("Error: bad arguments to `eof-object?`".to_string())
+    }
+}
+
+fn port_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Port(_) => true,
+            _              => false,
+        }))))
+    } else {
+        Err("Error: bad arguments to `port?`".to_string())
+    }
+}
+
+fn input_port_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Port(p) => p.is_input(),
+            _              => false,
+        }))))
+    } else {
+        Err("Error: bad arguments to `input-port?`".to_string())
+    }
+}
+
+fn output_port_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Port(p) => p.is_output(),
+            _              => false,
+        }))))
+    } else {
+        Err("Error: bad arguments to `output-port?`".to_string())
+    }
+}
+
+fn command_line(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use value;
+
+    if args.len() != 0 {
+        return Err("Error: bad arguments to `command-line`".to_string());
+    }
+
+    let command_line = heap.command_line().to_vec();
+    let strings : Vec<RootedValue> = command_line.into_iter()
+        .map(|arg| Value::new_string(heap, arg))
+        .collect();
+    Ok(Trampoline::Value(value::list(heap, strings.as_slice())))
+}
+
+/// Convert the optional argument to `exit` or `emergency-exit` into a process
+/// exit status.
+fn exit_status(args: &[RootedValue], name: &str) -> Result<i32, String> {
+    use std::i32;
+
+    match args {
+        []        => Ok(0),
+        [ref arg] => match **arg {
+            Value::Boolean(true)  => Ok(0),
+            Value::Boolean(false) => Ok(1),
+            Value::Integer(i) if i >= i32::MIN as i64 && i <= i32::MAX as i64 => {
+                Ok(i as i32)
+            },
+            _                     => Err(format!(
+                "Error: cannot use `{}` with a status of {}", name, **arg)),
+        },
+        _         => Err(format!("Error: bad arguments to `{}`", name)),
+    }
+}
+
+fn exit(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    // Rather than exiting the process from here, record the status and unwind
+    // back to whoever started evaluation, which is responsible for exiting.
+    let status = try!(exit_status(args.as_slice(), "exit"));
+    heap.request_exit(status);
+    Err(format!("Exiting with status {}", status))
+}
 
+fn emergency_exit(_: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use libc;
     use std::old_io;
 
+    let status = try!(exit_status(args.as_slice(), "emergency-exit"));
+    let _ = old_io::stdio::flush();
+    unsafe {
+        libc::exit(status);
+    }
+}
+
+fn get_environment_variable(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use std::env;
+
+    if let [ref arg] = args.as_slice() {
+        let name = try!(arg.to_string_ptr(heap).ok_or(
+            "Error: cannot use `get-environment-variable` with non-strings".to_string()));
+        Ok(Trampoline::Value(match env::var(name.as_slice()) {
+            Ok(val) => Value::new_string(heap, val),
+            Err(_)  => Rooted::new(heap, Value::new_boolean(false)),
+        }))
+    } else {
+        Err("Error: bad arguments to `get-environment-variable`".to_string())
+    }
+}
+
+fn get_environment_variables(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use std::env;
+    use value;
+
+    if args.len() != 0 {
+        return Err("Error: bad arguments to `get-environment-variables`".to_string());
+    }
+
+    let pairs : Vec<RootedValue> = env::vars()
+        .map(|(name, val)| {
+            let name = Value::new_string(heap, name);
+            let val = Value::new_string(heap, val);
+            Value::new_pair(heap, &name, &val)
+        })
+        .collect();
+    Ok(Trampoline::Value(value::list(heap, pairs.as_slice())))
+}
+
+/// The number of jiffies, as returned by `current-jiffy`, in a second.
+static JIFFIES_PER_SECOND : i64 = 1_000_000;
+
+fn current_second(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use clock;
+
+    if args.len() != 0 {
+        return Err("Error: bad arguments to `current-second`".to_string());
+    }
+    let seconds = clock::realtime_ns() as f64 / 1e9;
+    Ok(Trampoline::Value(Rooted::new(heap, Value::new_float(seconds))))
+}
+
+fn current_jiffy(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    use clock;
+
+    if args.len() != 0 {
+        return Err("Error: bad arguments to `current-jiffy`".to_string());
+    }
+    let ns_per_jiffy = 1_000_000_000 / JIFFIES_PER_SECOND as u64;
+    let jiffies = (clock::monotonic_ns() / ns_per_jiffy) as i64;
+    Ok(Trampoline::Value(Rooted::new(heap, Value::new_integer(jiffies))))
+}
+
+fn jiffies_per_second(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
     if args.len() != 0 {
-        return Err("`read` called with too many parameters".to_string());
+        return Err("Error: bad arguments to `jiffies-per-second`".to_string());
+    }
+    Ok(Trampoline::Value(Rooted::new(heap, Value::new_integer(JIFFIES_PER_SECOND))))
+}
+
+fn not(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Boolean(b) if b == false => true,
+            _                               => false,
+        }))))
+    } else {
+        Err("Error: bad arguments to `not`".to_string())
+    }
+}
+
+fn null_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(
+            Rooted::new(heap, Value::new_boolean(**arg == Value::EmptyList))))
+    } else {
+        Err("Error: bad arguments to `null?`".to_string())
+    }
+}
+
+fn pair_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Pair(_) => true,
+            _              => false,
+        }))))
+    } else {
+        Err("Error: bad arguments to `pair?`".to_string())
+    }
+}
+
+fn atom_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Pair(_) => false,
+            _              => true,
+        }))))
+    } else {
+        Err("Error: bad arguments to `atom?`".to_string())
+    }
+}
+
+fn eq_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref a, ref b] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(*a == *b))))
+    } else {
+        Err("Error: bad arguments to `eq?`".to_string())
+    }
+}
+
+fn eqv_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref a, ref b] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(a.eqv(&**b)))))
+    } else {
+        Err("Error: bad arguments to `eqv?`".to_string())
+    }
+}
+
+fn equal_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref a, ref b] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(a.equal(&**b)))))
+    } else {
+        Err("Error: bad arguments to `equal?`".to_string())
+    }
+}
+
+fn symbol_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Symbol(_) => true,
+            _                => false
+        }))))
+    } else {
+        Err("Error: bad arguments to `symbol?`".to_string())
+    }
+}
+
+fn number_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::Integer(_) => true,
+            Value::Float(_)   => true,
+            _                 => false
+        }))))
+    } else {
+        Err("Error: bad arguments to `number?`".to_string())
+    }
+}
+
+fn string_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
+            Value::String(_) => true,
+            _                => false
+        }))))
+    } else {
+        Err("Error: bad arguments to `string?`".to_string())
     }
+}
+
+/// The arguments of a binary arithmetic primitive: both exact, or both
+/// inexact because at least one of them was.
+enum Numbers {
+    Exact(i64, i64),
+    Inexact(f64, f64),
+}
+
+/// Get the number argument of the primitive with the given name, as a float.
+fn inexact_arg(arg: &RootedValue, name: &str) -> Result<f64, String> {
+    match **arg {
+        Value::Integer(i) => Ok(i as f64),
+        Value::Float(x)   => Ok(x.0),
+        _                 => Err(format!("Error: cannot use `{}` with non-numbers", name)),
+    }
+}
+
+/// Get the two number arguments of the binary arithmetic primitive with the
+/// given name. If either one is inexact, both are converted to floats.
+fn number_args(args: &[RootedValue], name: &str) -> Result<Numbers, String> {
+    if let [ref a, ref b] = args {
+        match (a.to_integer(), b.to_integer()) {
+            (Some(first), Some(second)) => Ok(Numbers::Exact(first, second)),
+            _                           => {
+                let first = try!(inexact_arg(a, name));
+                let second = try!(inexact_arg(b, name));
+                Ok(Numbers::Inexact(first, second))
+            },
+        }
+    } else {
+        Err(format!("Error: bad arguments to `{}`", name))
+    }
+}
+
+fn number_equal(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let result = match try!(number_args(args.as_slice(), "=")) {
+        Numbers::Exact(first, second)   => first == second,
+        Numbers::Inexact(first, second) => first == second,
+    };
+    Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(result))))
+}
+
+fn gt(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let result = match try!(number_args(args.as_slice(), ">")) {
+        Numbers::Exact(first, second)   => first > second,
+        Numbers::Inexact(first, second) => first > second,
+    };
+    Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(result))))
+}
+
+fn lt(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let result = match try!(number_args(args.as_slice(), "<")) {
+        Numbers::Exact(first, second)   => first < second,
+        Numbers::Inexact(first, second) => first < second,
+    };
+    Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(result))))
+}
+
+fn add(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let result = match try!(number_args(args.as_slice(), "+")) {
+        Numbers::Exact(first, second)   => Value::new_integer(first + second),
+        Numbers::Inexact(first, second) => Value::new_float(first + second),
+    };
+    Ok(Trampoline::Value(Rooted::new(heap, result)))
+}
+
+fn subtract(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let result = match try!(number_args(args.as_slice(), "-")) {
+        Numbers::Exact(first, second)   => Value::new_integer(first - second),
+        Numbers::Inexact(first, second) => Value::new_float(first - second),
+    };
+    Ok(Trampoline::Value(Rooted::new(heap, result)))
+}
+
+/// Exact division truncates, since we don't have rationals. Inexact division
+/// by zero produces an infinity or NaN rather than an error.
+fn divide(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let result = match try!(number_args(args.as_slice(), "/")) {
+        Numbers::Exact(_, 0)            => return Err("Error: divide by zero".to_string()),
+        Numbers::Exact(first, second)   => Value::new_integer(first / second),
+        Numbers::Inexact(first, second) => Value::new_float(first / second),
+    };
+    Ok(Trampoline::Value(Rooted::new(heap, result)))
+}
+
+fn multiply(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    let result = match try!(number_args(args.as_slice(), "*")) {
+        Numbers::Exact(first, second)   => Value::new_integer(first * second),
+        Numbers::Inexact(first, second) => Value::new_float(first * second),
+    };
+    Ok(Trampoline::Value(Rooted::new(heap, result)))
+}
 
-    let stdin = old_io::stdio::stdin();
-    let reader = Read::new(stdin, heap, "stdin".to_string());
-    for (_, read_result) in reader {
-        let form = try!(read_result);
-        return Ok(Trampoline::Value(form));
+fn exact_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let is_exact = match **arg {
+            Value::Integer(_) => true,
+            Value::Float(_)   => false,
+            _                 => {
+                return Err("Error: cannot use `exact?` with non-numbers".to_string());
+            },
+        };
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(is_exact))))
+    } else {
+        Err("Error: bad arguments to `exact?`".to_string())
     }
-
-    Ok(Trampoline::Value(heap.eof_symbol()))
 }
 
-fn not(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+fn inexact_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
     if let [ref arg] = args.as_slice() {
-        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
-            Value::Boolean(b) if b == false => true,
-            _                               => false,
-        }))))
+        let is_inexact = match **arg {
+            Value::Integer(_) => false,
+            Value::Float(_)   => true,
+            _                 => {
+                return Err("Error: cannot use `inexact?` with non-numbers".to_string());
+            },
+        };
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(is_inexact))))
     } else {
-        Err("Error: bad arguments to `not`".to_string())
+        Err("Error: bad arguments to `inexact?`".to_string())
     }
 }
 
-fn null_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+/// `(exact z)`: convert `z` to an exact integer. Since we don't have
+/// rationals, inexact numbers with a fractional part are an error.
+fn exact(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
     if let [ref arg] = args.as_slice() {
-        Ok(Trampoline::Value(
-            Rooted::new(heap, Value::new_boolean(**arg == Value::EmptyList))))
+        let i = match **arg {
+            Value::Integer(i)                                       => i,
+            // The bounds are -2^63 and 2^63, which are exactly representable.
+            Value::Float(x) if x.0.fract() == 0.0
+                && x.0 >= -9223372036854775808.0
+                && x.0 < 9223372036854775808.0                      => x.0 as i64,
+            Value::Float(_)                                         => {
+                return Err(format!("Error: `exact` cannot represent {} as an integer",
+                                   **arg));
+            },
+            _                                                       => {
+                return Err("Error: cannot use `exact` with non-numbers".to_string());
+            },
+        };
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_integer(i))))
     } else {
-        Err("Error: bad arguments to `null?`".to_string())
+        Err("Error: bad arguments to `exact`".to_string())
     }
 }
 
-fn pair_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+fn inexact(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
     if let [ref arg] = args.as_slice() {
-        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
-            Value::Pair(_) => true,
-            _              => false,
-        }))))
+        let x = try!(inexact_arg(arg, "inexact"));
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_float(x))))
     } else {
-        Err("Error: bad arguments to `pair?`".to_string())
+        Err("Error: bad arguments to `inexact`".to_string())
     }
 }
 
-fn atom_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+fn char_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
     if let [ref arg] = args.as_slice() {
         Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
-            Value::Pair(_) => false,
-            _              => true,
+            Value::Character(_) => true,
+            _                   => false
         }))))
     } else {
-        Err("Error: bad arguments to `atom?`".to_string())
+        Err("Error: bad arguments to `char?`".to_string())
     }
 }
 
-fn eq_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    if let [ref a, ref b] = args.as_slice() {
-        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(*a == *b))))
+fn char_to_integer(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let c = try!(arg.to_character().ok_or(
+            "Error: cannot use `char->integer` with non-characters".to_string()));
+        Ok(Trampoline::Value(
+            Rooted::new(heap, Value::new_integer(c as i64))))
     } else {
-        Err("Error: bad arguments to `eq?`".to_string())
+        Err("Error: bad arguments to `char->integer`".to_string())
     }
 }
 
-fn symbol_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+fn integer_to_char(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
     if let [ref arg] = args.as_slice() {
-        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
-            Value::Symbol(_) => true,
-            _                => false
-        }))))
+        let i = try!(arg.to_integer().ok_or(
+            "Error: cannot use `integer->char` with non-integers".to_string()));
+        if i < 0 || i > (::std::u32::MAX as i64) {
+            return Err(format!("Error: not a Unicode scalar value: {}", i));
+        }
+        let c = try!(::std::char::from_u32(i as u32).ok_or(
+            format!("Error: not a Unicode scalar value: {}", i)));
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_character(c))))
     } else {
-        Err("Error: bad arguments to `symbol?`".to_string())
+        Err("Error: bad arguments to `integer->char`".to_string())
     }
 }
 
-fn number_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+/// The code points of the zero digit of each run of ten Unicode decimal digits
+/// (general category Nd). Every decimal digit is `zero + n` for one of these
+/// zeros and `n` in `0..10`.
+static DECIMAL_DIGIT_ZEROS : [u32; 66] = [
+    0x0030, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66,
+    0x0BE6, 0x0C66, 0x0CE6, 0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040,
+    0x1090, 0x17E0, 0x1810, 0x1946, 0x19D0, 0x1A80, 0x1A90, 0x1B50, 0x1BB0,
+    0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0,
+    0xFF10, 0x104A0, 0x10D30, 0x11066, 0x110F0, 0x11136, 0x111D0, 0x112F0,
+    0x11450, 0x114D0, 0x11650, 0x116C0, 0x11730, 0x118E0, 0x11950, 0x11C50,
+    0x11D50, 0x11DA0, 0x16A60, 0x16AC0, 0x16B50, 0x1D7CE, 0x1D7D8, 0x1D7E2,
+    0x1D7EC, 0x1D7F6, 0x1E140, 0x1E2F0, 0x1E950, 0x1FBF0,
+];
+
+/// Get the numeric value of the given character if it is a Unicode decimal
+/// digit, or `None` if it is any other character.
+fn decimal_digit_value(c: char) -> Option<u32> {
+    let n = c as u32;
+    DECIMAL_DIGIT_ZEROS.iter()
+        .find(|&&zero| zero <= n && n < zero + 10)
+        .map(|&zero| n - zero)
+}
+
+/// Apply the simple Unicode case folding to the given character.
+fn fold_case(c: char) -> char {
+    c.to_lowercase()
+}
+
+/// Test a unary predicate on a single character argument.
+fn test_character<F: Fn(char) -> bool>(heap: &mut Heap,
+                                       args: Vec<RootedValue>,
+                                       name: &str,
+                                       predicate: F) -> TrampolineResult {
     if let [ref arg] = args.as_slice() {
-        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
-            Value::Integer(_) => true,
-            _                 => false
-        }))))
+        let c = try!(arg.to_character().ok_or(
+            format!("Error: cannot use `{}` with non-characters", name)));
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(predicate(c)))))
     } else {
-        Err("Error: bad arguments to `number?`".to_string())
+        Err(format!("Error: bad arguments to `{}`", name))
     }
 }
 
-fn string_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+/// Map a single character argument to a new character.
+fn map_character<F: Fn(char) -> char>(heap: &mut Heap,
+                                      args: Vec<RootedValue>,
+                                      name: &str,
+                                      f: F) -> TrampolineResult {
     if let [ref arg] = args.as_slice() {
-        Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(match **arg {
-            Value::String(_) => true,
-            _                => false
-        }))))
+        let c = try!(arg.to_character().ok_or(
+            format!("Error: cannot use `{}` with non-characters", name)));
+        Ok(Trampoline::Value(Rooted::new(heap, Value::new_character(f(c)))))
     } else {
-        Err("Error: bad arguments to `string?`".to_string())
+        Err(format!("Error: bad arguments to `{}`", name))
     }
 }
 
-fn number_equal(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    if let [ref a, ref b] = args.as_slice() {
-        let first = try!(a.to_integer().ok_or(
-            "Error: cannot use `=` with non-numbers".to_string()));
-        let second = try!(b.to_integer().ok_or(
-            "Error: cannot use `=` with non-numbers".to_string()));
-        Ok(Trampoline::Value(
-            Rooted::new(heap, Value::new_boolean(first == second))))
-    } else {
-        Err("Error: bad arguments to `=`".to_string())
+/// Compare each adjacent pair of two or more character arguments with `cmp`,
+/// optionally case folding the characters first.
+fn compare_characters<F: Fn(char, char) -> bool>(heap: &mut Heap,
+                                                 args: Vec<RootedValue>,
+                                                 name: &str,
+                                                 case_insensitive: bool,
+                                                 cmp: F) -> TrampolineResult {
+    if args.len() < 2 {
+        return Err(format!("Error: bad arguments to `{}`", name));
     }
-}
 
-fn gt(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    if let [ref a, ref b] = args.as_slice() {
-        let first = try!(a.to_integer().ok_or(
-            "Error: cannot use `>` with non-numbers".to_string()));
-        let second = try!(b.to_integer().ok_or(
-            "Error: cannot use `>` with non-numbers".to_string()));
-        Ok(Trampoline::Value(
-            Rooted::new(heap, Value::new_boolean(first > second))))
-    } else {
-        Err("Error: bad arguments to `>`".to_string())
+    let mut chars = Vec::with_capacity(args.len());
+    for arg in args.iter() {
+        let c = try!(arg.to_character().ok_or(
+            format!("Error: cannot use `{}` with non-characters", name)));
+        chars.push(if case_insensitive { fold_case(c) } else { c });
     }
+
+    let result = chars.windows(2).all(|w| cmp(w[0], w[1]));
+    Ok(Trampoline::Value(Rooted::new(heap, Value::new_boolean(result))))
 }
 
-fn lt(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    if let [ref a, ref b] = args.as_slice() {
-        let first = try!(a.to_integer().ok_or(
-            "Error: cannot use `<` with non-numbers".to_string()));
-        let second = try!(b.to_integer().ok_or(
-            "Error: cannot use `<` with non-numbers".to_string()));
-        Ok(Trampoline::Value(
-            Rooted::new(heap, Value::new_boolean(first < second))))
-    } else {
-        Err("Error: bad arguments to `<`".to_string())
-    }
+fn char_equal(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char=?", false, |a, b| a == b)
 }
 
-fn add(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    if let [ref a, ref b] = args.as_slice() {
-        let first = try!(a.to_integer().ok_or(
-            "Error: cannot use `+` with non-numbers".to_string()));
-        let second = try!(b.to_integer().ok_or(
-            "Error: cannot use `+` with non-numbers".to_string()));
-        Ok(Trampoline::Value(
-            Rooted::new(heap, Value::new_integer(first + second))))
-    } else {
-        Err("Error: bad arguments to `+`".to_string())
-    }
+fn char_lt(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char<?", false, |a, b| a < b)
 }
 
-fn subtract(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    if let [ref a, ref b] = args.as_slice() {
-        let first = try!(a.to_integer().ok_or(
-            "Error: cannot use `-` with non-numbers".to_string()));
-        let second = try!(b.to_integer().ok_or(
-            "Error: cannot use `-` with non-numbers".to_string()));
-        Ok(Trampoline::Value(
-            Rooted::new(heap, Value::new_integer(first - second))))
-    } else {
-        Err("Error: bad arguments to `-`".to_string())
-    }
+fn char_gt(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char>?", false, |a, b| a > b)
 }
 
-fn divide(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    if let [ref a, ref b] = args.as_slice() {
-        let first = try!(a.to_integer().ok_or(
-            "Error: cannot use `/` with non-numbers".to_string()));
-        let second = try!(b.to_integer().ok_or(
-            "Error: cannot use `/` with non-numbers".to_string()));
-        if second == 0 {
-            return Err("Error: divide by zero".to_string());
-        }
-        Ok(Trampoline::Value(
-            Rooted::new(heap, Value::new_integer(first / second))))
-    } else {
-        Err("Error: bad arguments to `/`".to_string())
-    }
+fn char_lte(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char<=?", false, |a, b| a <= b)
 }
 
-fn multiply(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
-    if let [ref a, ref b] = args.as_slice() {
-        let first = try!(a.to_integer().ok_or(
-            "Error: cannot use `*` with non-numbers".to_string()));
-        let second = try!(b.to_integer().ok_or(
-            "Error: cannot use `*` with non-numbers".to_string()));
-        Ok(Trampoline::Value(
-            Rooted::new(heap, Value::new_integer(first * second))))
+fn char_gte(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char>=?", false, |a, b| a >= b)
+}
+
+fn char_ci_equal(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char-ci=?", true, |a, b| a == b)
+}
+
+fn char_ci_lt(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char-ci<?", true, |a, b| a < b)
+}
+
+fn char_ci_gt(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char-ci>?", true, |a, b| a > b)
+}
+
+fn char_ci_lte(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char-ci<=?", true, |a, b| a <= b)
+}
+
+fn char_ci_gte(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    compare_characters(heap, args, "char-ci>=?", true, |a, b| a >= b)
+}
+
+fn char_alphabetic_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    test_character(heap, args, "char-alphabetic?", |c| c.is_alphabetic())
+}
+
+fn char_numeric_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    test_character(heap, args, "char-numeric?", |c| decimal_digit_value(c).is_some())
+}
+
+fn char_whitespace_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    test_character(heap, args, "char-whitespace?", |c| c.is_whitespace())
+}
+
+fn char_upper_case_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    test_character(heap, args, "char-upper-case?", |c| c.is_uppercase())
+}
+
+fn char_lower_case_question(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    test_character(heap, args, "char-lower-case?", |c| c.is_lowercase())
+}
+
+fn char_upcase(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    map_character(heap, args, "char-upcase", |c| c.to_uppercase())
+}
+
+fn char_downcase(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    map_character(heap, args, "char-downcase", |c| c.to_lowercase())
+}
+
+fn char_foldcase(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    map_character(heap, args, "char-foldcase", fold_case)
+}
+
+fn digit_value(heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
+    if let [ref arg] = args.as_slice() {
+        let c = try!(arg.to_character().ok_or(
+            "Error: cannot use `digit-value` with non-characters".to_string()));
+        Ok(Trampoline::Value(Rooted::new(heap, match decimal_digit_value(c) {
+            Some(d) => Value::new_integer(d as i64),
+            None    => Value::new_boolean(false),
+        })))
     } else {
-        Err("Error: bad arguments to `*`".to_string())
+        Err("Error: bad arguments to `digit-value`".to_string())
     }
 }
 
@@ -339,18 +2443,134 @@ pub fn define_primitives(env: &mut Environment, act: &mut ActivationPtr) {
 
     define_primitive(env, act, "list", list);
     define_primitive(env, act, "length", length);
+    define_primitive(env, act, "map", map);
+    define_primitive(env, act, "for-each", for_each);
+    define_primitive(env, act, "append", append);
+    define_primitive(env, act, "append!", append_bang);
+    define_primitive(env, act, "reverse", reverse);
+    define_primitive(env, act, "list-tail", list_tail);
+    define_primitive(env, act, "list-ref", list_ref);
+    define_primitive(env, act, "list-copy", list_copy);
+    define_primitive(env, act, "last-pair", last_pair);
+    define_primitive(env, act, "memq", memq);
+    define_primitive(env, act, "memv", memv);
+    define_primitive(env, act, "member", member);
+    define_primitive(env, act, "assq", assq);
+    define_primitive(env, act, "assv", assv);
+    define_primitive(env, act, "assoc", assoc);
+
+    define_primitive(env, act, "filter", filter);
+    define_primitive(env, act, "remove", remove);
+    define_primitive(env, act, "partition", partition);
+    define_primitive(env, act, "reduce", reduce);
+    define_primitive(env, act, "fold", fold);
+    define_primitive(env, act, "fold-right", fold_right);
+    define_primitive(env, act, "delete", delete);
+    define_primitive(env, act, "delete-duplicates", delete_duplicates);
+    define_primitive(env, act, "iota", iota);
+    define_primitive(env, act, "find", find);
+    define_primitive(env, act, "find-tail", find_tail);
+    define_primitive(env, act, "any", any);
+    define_primitive(env, act, "every", every);
+    define_primitive(env, act, "count", count);
+    define_primitive(env, act, "take", take);
+    define_primitive(env, act, "drop", drop);
+    define_primitive(env, act, "split-at", split_at);
+    define_primitive(env, act, "last", last);
+    define_primitive(env, act, "append-map", append_map);
+    define_primitive(env, act, "filter-map", filter_map);
+
+    define_primitive(env, act, "make-hash-table", make_hash_table);
+    define_primitive(env, act, "make-eq-hash-table", make_eq_hash_table);
+    define_primitive(env, act, "make-eqv-hash-table", make_eqv_hash_table);
+    define_primitive(env, act, "make-equal-hash-table", make_equal_hash_table);
+    define_primitive(env, act, "make-string-hash-table", make_string_hash_table);
+    define_primitive(env, act, "hash-table?", hash_table_question);
+    define_primitive(env, act, "hash-table-ref", hash_table_ref);
+    define_primitive(env, act, "hash-table-ref/default", hash_table_ref_default);
+    define_primitive(env, act, "hash-table-set!", hash_table_set_bang);
+    define_primitive(env, act, "hash-table-delete!", hash_table_delete_bang);
+    define_primitive(env, act, "hash-table-contains?", hash_table_contains_question);
+    define_primitive(env, act, "hash-table-update!", hash_table_update_bang);
+    define_primitive(env, act, "hash-table-update!/default", hash_table_update_bang_default);
+    define_primitive(env, act, "hash-table-count", hash_table_count);
+    define_primitive(env, act, "hash-table-keys", hash_table_keys);
+    define_primitive(env, act, "hash-table-values", hash_table_values);
+    define_primitive(env, act, "hash-table->alist", hash_table_to_alist);
+    define_primitive(env, act, "hash-table-walk", hash_table_walk);
 
     define_primitive(env, act, "apply", apply);
 
     define_primitive(env, act, "error", error);
+    define_primitive(env, act, "raise", raise);
+    define_primitive(env, act, "raise-continuable", raise_continuable);
+    define_primitive(env, act, "with-exception-handler", with_exception_handler);
+    define_primitive(env, act, "error-object?", error_object_question);
+    define_primitive(env, act, "error-object-message", error_object_message);
+    define_primitive(env, act, "error-object-irritants", error_object_irritants);
+    define_primitive(env, act, "file-error?", file_error_question);
+    define_primitive(env, act, "read-error?", read_error_question);
     define_primitive(env, act, "print", print);
     define_primitive(env, act, "read", read);
 
+    define_primitive(env, act, "current-input-port", current_input_port);
+    define_primitive(env, act, "current-output-port", current_output_port);
+    define_primitive(env, act, "current-error-port", current_error_port);
+    define_primitive(env, act, "read-char", read_char);
+    define_primitive(env, act, "peek-char", peek_char);
+    define_primitive(env, act, "read-line", read_line);
+    define_primitive(env, act, "read-string", read_string);
+    define_primitive(env, act, "write-char", write_char);
+    define_primitive(env, act, "write-string", write_string);
+    define_primitive(env, act, "newline", newline);
+    define_primitive(env, act, "write", write);
+    define_primitive(env, act, "write-shared", write_shared);
+    define_primitive(env, act, "write-simple", write_simple);
+    define_primitive(env, act, "display", display);
+    define_primitive(env, act, "pretty-print", pretty_print);
+    define_primitive(env, act, "close-port", close_port);
+    define_primitive(env, act, "eof-object", eof_object);
+    define_primitive(env, act, "eof-object?", eof_object_question);
+    define_primitive(env, act, "port?", port_question);
+    define_primitive(env, act, "input-port?", input_port_question);
+    define_primitive(env, act, "output-port?", output_port_question);
+    define_primitive(env, act, "binary-port?", binary_port_question);
+    define_primitive(env, act, "textual-port?", textual_port_question);
+    define_primitive(env, act, "read-u8", read_u8);
+    define_primitive(env, act, "peek-u8", peek_u8);
+    define_primitive(env, act, "write-u8", write_u8);
+
+    define_primitive(env, act, "open-input-file", open_input_file);
+    define_primitive(env, act, "open-binary-input-file", open_binary_input_file);
+    define_primitive(env, act, "open-output-file", open_output_file);
+    define_primitive(env, act, "open-binary-output-file", open_binary_output_file);
+    define_primitive(env, act, "call-with-input-file", call_with_input_file);
+    define_primitive(env, act, "call-with-output-file", call_with_output_file);
+    define_primitive(env, act, "with-output-to-file", with_output_to_file);
+    define_primitive(env, act, "file-exists?", file_exists_question);
+    define_primitive(env, act, "delete-file", delete_file);
+
+    define_primitive(env, act, "open-input-string", open_input_string);
+    define_primitive(env, act, "open-output-string", open_output_string);
+    define_primitive(env, act, "get-output-string", get_output_string);
+    define_primitive(env, act, "with-output-to-string", with_output_to_string);
+
+    define_primitive(env, act, "command-line", command_line);
+    define_primitive(env, act, "exit", exit);
+    define_primitive(env, act, "emergency-exit", emergency_exit);
+    define_primitive(env, act, "get-environment-variable", get_environment_variable);
+    define_primitive(env, act, "get-environment-variables", get_environment_variables);
+    define_primitive(env, act, "current-second", current_second);
+    define_primitive(env, act, "current-jiffy", current_jiffy);
+    define_primitive(env, act, "jiffies-per-second", jiffies_per_second);
+
     define_primitive(env, act, "not", not);
     define_primitive(env, act, "null?", null_question);
     define_primitive(env, act, "pair?", pair_question);
     define_primitive(env, act, "atom?", atom_question);
     define_primitive(env, act, "eq?", eq_question);
+    define_primitive(env, act, "eqv?", eqv_question);
+    define_primitive(env, act, "equal?", equal_question);
     define_primitive(env, act, "symbol?", symbol_question);
     define_primitive(env, act, "number?", number_question);
     define_primitive(env, act, "string?", string_question);
@@ -363,6 +2583,34 @@ pub fn define_primitives(env: &mut Environment, act: &mut ActivationPtr) {
     define_primitive(env, act, "-", subtract);
     define_primitive(env, act, "/", divide);
     define_primitive(env, act, "*", multiply);
+
+    define_primitive(env, act, "exact?", exact_question);
+    define_primitive(env, act, "inexact?", inexact_question);
+    define_primitive(env, act, "exact", exact);
+    define_primitive(env, act, "inexact", inexact);
+
+    define_primitive(env, act, "char?", char_question);
+    define_primitive(env, act, "char->integer", char_to_integer);
+    define_primitive(env, act, "integer->char", integer_to_char);
+    define_primitive(env, act, "char=?", char_equal);
+    define_primitive(env, act, "char<?", char_lt);
+    define_primitive(env, act, "char>?", char_gt);
+    define_primitive(env, act, "char<=?", char_lte);
+    define_primitive(env, act, "char>=?", char_gte);
+    define_primitive(env, act, "char-ci=?", char_ci_equal);
+    define_primitive(env, act, "char-ci<?", char_ci_lt);
+    define_primitive(env, act, "char-ci>?", char_ci_gt);
+    define_primitive(env, act, "char-ci<=?", char_ci_lte);
+    define_primitive(env, act, "char-ci>=?", char_ci_gte);
+    define_primitive(env, act, "char-alphabetic?", char_alphabetic_question);
+    define_primitive(env, act, "char-numeric?", char_numeric_question);
+    define_primitive(env, act, "char-whitespace?", char_whitespace_question);
+    define_primitive(env, act, "char-upper-case?", char_upper_case_question);
+    define_primitive(env, act, "char-lower-case?", char_lower_case_question);
+    define_primitive(env, act, "char-upcase", char_upcase);
+    define_primitive(env, act, "char-downcase", char_downcase);
+    define_primitive(env, act, "char-foldcase", char_foldcase);
+    define_primitive(env, act, "digit-value", digit_value);
 }
 
 // TESTS -----------------------------------------------------------------------
@@ -475,6 +2723,171 @@ mod tests {
                            \t(1 2)");
     }
 
+    #[test]
+    fn test_primitives_command_line() {
+        let heap = &mut Heap::new();
+        heap.set_command_line(vec!("script.scm".to_string(), "-v".to_string()));
+        let result = evaluate_file(heap, "./tests/test_primitives_command_line.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result), "(\"script.scm\" \"-v\")");
+    }
+
+    #[test]
+    fn test_primitives_exit() {
+        let heap = &mut Heap::new();
+        assert!(evaluate_file(heap, "./tests/test_primitives_exit.scm").is_err());
+        assert_eq!(heap.exit_status(), Some(3));
+    }
+
+    #[test]
+    fn test_primitives_exit_out_of_range() {
+        let heap = &mut Heap::new();
+        let error = evaluate_file(heap, "./tests/test_primitives_exit_out_of_range.scm")
+            .err()
+            .expect("Should get an error evaluating this file.");
+        assert_eq!(error, "./tests/test_primitives_exit_out_of_range.scm:1:1:\n\
+                           Error: cannot use `exit` with a status of 4294967297");
+        assert_eq!(heap.exit_status(), None);
+    }
+
+    #[test]
+    fn test_primitives_current_second() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_current_second.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result), "(#t #t)");
+    }
+
+    #[test]
+    fn test_primitives_get_environment_variable() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_get_environment_variable.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        let pair = result.to_pair(heap)
+            .expect("Result should be a pair");
+        assert_eq!(*pair.car(heap), Value::new_boolean(true));
+        assert_eq!(*pair.cdr(heap), Value::new_boolean(false));
+    }
+
+    #[test]
+    fn test_primitives_jiffies() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_jiffies.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(*result, Value::new_boolean(true));
+    }
+
+    #[test]
+    fn test_primitives_ports() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_ports.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "(#t #t #t #f #t #f #f #<eof> #<input-port stdin> #<output-port stderr>)");
+    }
+
+    #[test]
+    fn test_primitives_file_io() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_file_io.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result), "(\"hello\" \"bye\" #t 98 #f)");
+    }
+
+    #[test]
+    fn test_primitives_missing_file() {
+        let heap = &mut Heap::new();
+        let error = evaluate_file(heap, "./tests/test_primitives_missing_file.scm")
+            .err()
+            .expect("Should get an error evaluating this file.");
+        assert!(error.starts_with("./tests/test_primitives_missing_file.scm:1:1:\n\
+                                   Error: could not open file ./tests/no-such-file \
+                                   for reading: "));
+    }
+
+    #[test]
+    fn test_primitives_exception_handlers() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_exception_handlers.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "(\"Error: exception handler returned from non-continuable `raise`\" \
+                    #t x 41 41)");
+    }
+
+    #[test]
+    fn test_primitives_string_ports() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_string_ports.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "((foo bar) #\\a \"bc\" \"line\" #t \"x=1\\n\" \"hi\")");
+    }
+
+    #[test]
+    fn test_primitives_write_display() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_write_display.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "\"(\\\"hi\\\" #\\\\x |a b|) (hi x a b) (#0=(1) #0#)\"");
+    }
+
+    #[test]
+    fn test_primitives_lists() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_lists.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "((1 4 9) (11 22) 32 (1 2 3 4 . 5) () (1 2 3 5 6) (3 2 1) (3 4) b \
+                    (1 2 . 3) (3) (c d) (3) (b 2) #f #t)");
+    }
+
+    #[test]
+    fn test_primitives_srfi_1() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_srfi_1.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "((1 2 3 4 5 6) (2 4 6) (1 3 5) ((1 3 5) (2 4 6)) 21 0 (3 2 1) 11 \
+                    (1 2 3) (1 2 4 5 6) (1 2 3) (a b c) (0 -2 -4) 4 (4 5) 4 #t #t 2 \
+                    (1 2) (5 6) ((1 2 3) (4 5 6)) 6 (1 1 2 2) (10 30 50))");
+    }
+
+    #[test]
+    fn test_primitives_hash_tables() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_hash_tables.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "(#t 2 1 gone 2 #f (a) #<hash-table string=? 1> 2 default 11 1 #f 12 (1) \
+                    ((a . 1)) #f)");
+    }
+
+    #[test]
+    fn test_primitives_pretty_print() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_pretty_print.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "\"(define (my-map f lst)\\n  \
+                    (if (null? lst) (quote ()) (cons (f (car lst)) (my-map f (cdr lst)))))\\n\
+                    (a b c)\\n\"");
+    }
+
     #[test]
     fn test_primitives_not() {
         let heap = &mut Heap::new();
@@ -508,6 +2921,16 @@ mod tests {
         assert_eq!(*result, Value::new_integer(42));
     }
 
+    #[test]
+    fn test_primitives_inexact() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_inexact.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "(3.5 3.0 -0.5 0.25 3 +inf.0 #t #t #t #f #t 4 3.0)");
+    }
+
     #[test]
     fn test_primitives_pair() {
         let heap = &mut Heap::new();
@@ -544,6 +2967,16 @@ mod tests {
         assert_eq!(*pair.cdr(heap), Value::new_boolean(false));
     }
 
+    #[test]
+    fn test_primitives_equal() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_equal.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        assert_eq!(format!("{}", *result),
+                   "(#t #t #f #t #f #t #t (\"b\" 2) ((b) c) (\"a\" \"b\"))");
+    }
+
     #[test]
     fn test_primitives_symbol_question() {
         let heap = &mut Heap::new();
@@ -615,4 +3048,65 @@ mod tests {
         assert_eq!(*pair.car(heap), Value::new_boolean(true));
         assert_eq!(*pair.cdr(heap), Value::new_boolean(false));
     }
+
+    #[test]
+    fn test_primitives_char_question() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_char_question.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        let pair = result.to_pair(heap)
+            .expect("Result should be a pair");
+        assert_eq!(*pair.car(heap), Value::new_boolean(true));
+        assert_eq!(*pair.cdr(heap), Value::new_boolean(false));
+    }
+
+    #[test]
+    fn test_primitives_char_integer() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_char_integer.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        let results : Vec<Value> = result.iter()
+            .map(|v| v.ok().expect("Result should be a proper list"))
+            .collect();
+        assert_eq!(results, vec!(Value::new_integer(65),
+                                 Value::new_integer(955),
+                                 Value::new_boolean(true)));
+    }
+
+    #[test]
+    fn test_primitives_char_compare() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_char_compare.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        let results : Vec<Value> = result.iter()
+            .map(|v| v.ok().expect("Result should be a proper list"))
+            .collect();
+        assert_eq!(results, vec!(Value::new_boolean(true),
+                                 Value::new_boolean(false),
+                                 Value::new_boolean(true),
+                                 Value::new_boolean(true),
+                                 Value::new_boolean(false)));
+    }
+
+    #[test]
+    fn test_primitives_char_unicode() {
+        let heap = &mut Heap::new();
+        let result = evaluate_file(heap, "./tests/test_primitives_char_unicode.scm")
+            .ok()
+            .expect("Should be able to eval a file.");
+        let results : Vec<Value> = result.iter()
+            .map(|v| v.ok().expect("Result should be a proper list"))
+            .collect();
+        assert_eq!(results, vec!(Value::new_boolean(true),
+                                 Value::new_boolean(true),
+                                 Value::new_integer(1),
+                                 Value::new_boolean(false),
+                                 Value::new_boolean(true),
+                                 Value::new_integer(923),
+                                 Value::new_character('a'),
+                                 Value::new_character('z')));
+    }
 }
@@ -15,23 +15,29 @@
 //! Parsing values.
 
 use std::cell::{RefCell};
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::f64;
 use std::fmt;
-use std::iter::{Peekable};
 use std::old_io::{BufferedReader, File, IoError, IoErrorKind, IoResult, MemReader};
 
+use diagnostic::{Diagnostic};
 use heap::{Heap, Rooted};
-use value::{list, RootedValue, SchemeResult, Value};
+use value::{list, ConsPtr, RootedValue, SchemeResult, Value};
 
 /// `CharReader` reads characters one at a time from the given input `Reader`.
 struct CharReader<R> {
     reader: BufferedReader<R>,
+    /// The error that ended the input early, if any.
+    error: Option<IoError>,
 }
 
 impl<R: Reader> CharReader<R> {
     /// Create a new `CharReader` instance.
     pub fn new(reader: R) -> CharReader<R> {
         CharReader {
-            reader: BufferedReader::new(reader)
+            reader: BufferedReader::new(reader),
+            error: None,
         }
     }
 }
@@ -40,12 +46,20 @@ impl<R: Reader> Iterator for CharReader<R> {
     type Item = char;
 
     /// Returns `Some(c)` for each character `c` from the input reader. Upon
-    /// reaching EOF, returns `None`.
+    /// reaching EOF, returns `None`. Other errors are treated like EOF, and
+    /// saved in `self.error` to be reported.
     fn next(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+
         match self.reader.read_char() {
             Ok(c)                   => Some(c),
             Err(ref e) if is_eof(e) => None,
-            Err(e)                  => panic!("IO ERROR! {}", e),
+            Err(e)                  => {
+                self.error = Some(e);
+                None
+            },
         }
     }
 }
@@ -65,6 +79,12 @@ fn is_delimiter(c: &char) -> bool {
     c.is_whitespace() || is_comment(c) || *c == ')' || *c == '('
 }
 
+/// Return true if the character is whitespace that does not end a line, false
+/// otherwise.
+fn is_intraline_whitespace(c: &char) -> bool {
+    *c != '\n' && *c != '\r' && c.is_whitespace()
+}
+
 /// Return true if we have EOF (`None`) or a delimiting character, false
 /// otherwise.
 fn is_eof_or_delimiter(oc: &Option<char>) -> bool {
@@ -75,6 +95,97 @@ fn is_eof_or_delimiter(oc: &Option<char>) -> bool {
     }
 }
 
+/// Return true if the character is the second character of a radix or
+/// exactness prefix of a number, e.g. the 'x' in `#x1F`, false otherwise.
+fn is_number_prefix(c: &char) -> bool {
+    match c.to_lowercase() {
+        'x' | 'o' | 'b' | 'd' | 'e' | 'i' => true,
+        _                                 => false,
+    }
+}
+
+/// Return true if the character marks the start of a number's exponent, false
+/// otherwise.
+fn is_exponent_marker(c: &char) -> bool {
+    *c == 'e' || *c == 'E'
+}
+
+/// Return true if the character `c` followed by the character `d` begins an
+/// unprefixed number, e.g. "5", "-5", "+5", or ".5", false otherwise.
+fn is_number_start(c: &char, d: &Option<char>) -> bool {
+    let d_is_digit = d.map(|d| d.is_digit(10)).unwrap_or(false);
+    match *c {
+        '+' | '-' | '.' => d_is_digit,
+        c               => c.is_digit(10),
+    }
+}
+
+/// The error message for integer literals that do not fit in an `i64`.
+fn integer_too_large() -> String {
+    "Integer literal is too large".to_string()
+}
+
+/// Compute the exact integer with the given digits in the given radix, times
+/// `10^power`. Returns an error message if the result would overflow or is not
+/// an integer.
+fn exact_integer(digits: &[i64], radix: u32, power: i64) -> Result<i64, String> {
+    let mut value : i64 = 0;
+    for d in digits.iter() {
+        value = try!(value.checked_mul(radix as i64)
+                     .and_then(|v| v.checked_add(*d))
+                     .ok_or_else(integer_too_large));
+    }
+
+    if value == 0 {
+        return Ok(0);
+    }
+
+    if power >= 0 {
+        for _ in range(0, power) {
+            value = try!(value.checked_mul(10).ok_or_else(integer_too_large));
+        }
+    } else {
+        for _ in range(0, -power) {
+            if value % 10 != 0 {
+                return Err("Exact non-integer numbers are not supported yet".to_string());
+            }
+            value = value / 10;
+        }
+    }
+
+    Ok(value)
+}
+
+/// Compute the inexact real with the given digits in the given radix, times
+/// `10^power`. When both the mantissa and the power of ten are exactly
+/// representable as floats, which covers most literals written by hand, the
+/// result is correctly rounded.
+fn inexact_real(digits: &[i64], radix: u32, power: i64) -> f64 {
+    let mantissa = digits.iter().fold(0.0, |m, d| m * (radix as f64) + (*d as f64));
+    if mantissa == 0.0 {
+        return 0.0;
+    }
+
+    // Beyond this, every power of ten over- or underflows anyway.
+    let scale = 10.0f64.powi(cmp::min(power.abs(), 400) as i32);
+    if power >= 0 {
+        mantissa * scale
+    } else {
+        mantissa / scale
+    }
+}
+
+/// Get the value of the infinity or NaN with the given name, e.g. `+inf.0`.
+/// These are spelled like symbols, so `read_symbol` checks for them.
+fn special_float(name: &str) -> Option<f64> {
+    match name {
+        "+inf.0"            => Some(f64::INFINITY),
+        "-inf.0"            => Some(f64::NEG_INFINITY),
+        "+nan.0" | "-nan.0" => Some(f64::NAN),
+        _                   => None,
+    }
+}
+
 fn is_symbol_initial(c: &char) -> bool {
     c.is_alphabetic() || is_symbol_special_initial(c) || is_symbol_peculiar(c)
 }
@@ -93,15 +204,99 @@ fn is_symbol_subsequent(c: &char) -> bool {
     is_symbol_initial(c) || c.is_digit(10) || *c == '.' || *c == '+' || *c == '-'
 }
 
-/// A source location.
+/// Return true if the given symbol name reads back in as the same symbol
+/// without being surrounded by vertical bars, false otherwise.
+pub fn is_plain_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if is_symbol_initial(&c) => {
+            !is_number_start(&c, &chars.clone().next())
+                && special_float(name).is_none()
+                && chars.all(|c| is_symbol_subsequent(&c))
+        },
+        _                                => false,
+    }
+}
+
+/// Describe the kind of literal that is surrounded by the given delimiter, for
+/// use in error messages.
+fn literal_kind(delimiter: char) -> &'static str {
+    if delimiter == '|' {
+        "symbol"
+    } else {
+        "string"
+    }
+}
+
+/// The named characters, e.g. `#\newline`, and the characters they denote.
+static CHARACTER_NAMES : [(&'static str, char); 9] = [
+    ("alarm",     '\x07'),
+    ("backspace", '\x08'),
+    ("delete",    '\x7f'),
+    ("escape",    '\x1b'),
+    ("newline",   '\n'),
+    ("null",      '\0'),
+    ("return",    '\r'),
+    ("space",     ' '),
+    ("tab",       '\t'),
+];
+
+/// Get the character denoted by the given character name, if there is one.
+fn named_character(name: &str) -> Option<char> {
+    CHARACTER_NAMES.iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, c)| c)
+}
+
+/// Get the name of the given character, if it has one. This is the inverse of
+/// reading a named character literal.
+pub fn character_name(c: char) -> Option<&'static str> {
+    CHARACTER_NAMES.iter()
+        .find(|&&(_, d)| d == c)
+        .map(|&(n, _)| n)
+}
+
+/// Parse the hex digits of a hex scalar value, e.g. the "41" in `#\x41`, into
+/// the character it denotes. Returns `None` if the digits are malformed or do
+/// not denote a Unicode scalar value.
+fn hex_scalar_value(digits: &str) -> Option<char> {
+    if digits.is_empty() || digits.len() > 6 {
+        return None;
+    }
+
+    let mut n : u32 = 0;
+    for c in digits.chars() {
+        match c.to_digit(16) {
+            None    => return None,
+            Some(d) => n = (n * 16) + d,
+        }
+    }
+
+    ::std::char::from_u32(n)
+}
+
+/// A source location: the span of source text from a start position up to, but
+/// not including, an end position. The location of a single point in the
+/// source has the same start and end.
 #[derive(Debug)]
 pub struct Location {
     /// The source file.
     pub file: String,
-    /// 1-based line number.
+    /// Whether `file` is a file on disk that the source was read from, rather
+    /// than the name of some other source, such as `stdin` or `-e`.
+    pub is_file: bool,
+    /// 1-based line number of the start of the span.
     pub line: u64,
-    /// 1-based column number.
-    pub column: u64
+    /// 1-based column number of the start of the span.
+    pub column: u64,
+    /// 0-based byte offset of the start of the span.
+    pub offset: u64,
+    /// 1-based line number of the end of the span.
+    pub end_line: u64,
+    /// 1-based column number of the end of the span.
+    pub end_column: u64,
+    /// 0-based byte offset of the end of the span.
+    pub end_offset: u64,
 }
 
 impl Location {
@@ -109,8 +304,13 @@ impl Location {
     pub fn new(file: String) -> Location {
         Location {
             file: file,
+            is_file: false,
             line: 1,
             column: 1,
+            offset: 0,
+            end_line: 1,
+            end_column: 1,
+            end_offset: 0,
         }
     }
 
@@ -120,8 +320,26 @@ impl Location {
         let mut loc = Location::new("<unknown source location>".to_string());
         loc.line = 0;
         loc.column = 0;
+        loc.end_line = 0;
+        loc.end_column = 0;
         loc
     }
+
+    /// Create a new `Location` spanning from the start of this location to the
+    /// end of the given location.
+    pub fn to(&self, end: &Location) -> Location {
+        let mut loc = self.clone();
+        loc.end_line = end.end_line;
+        loc.end_column = end.end_column;
+        loc.end_offset = end.end_offset;
+        loc
+    }
+
+    /// Return true if this location is a placeholder for an unknown location,
+    /// false otherwise.
+    pub fn is_unknown(&self) -> bool {
+        self.line == 0
+    }
 }
 
 impl fmt::Display for Location {
@@ -132,10 +350,16 @@ impl fmt::Display for Location {
 
 impl Clone for Location {
     fn clone(&self) -> Self {
-        let mut new_loc = Location::new(self.file.clone());
-        new_loc.line = self.line;
-        new_loc.column = self.column;
-        new_loc
+        Location {
+            file: self.file.clone(),
+            is_file: self.is_file,
+            line: self.line,
+            column: self.column,
+            offset: self.offset,
+            end_line: self.end_line,
+            end_column: self.end_column,
+            end_offset: self.end_offset,
+        }
     }
 }
 
@@ -144,25 +368,92 @@ pub type SchemeResultAndLocation = (Location, SchemeResult);
 
 /// `Read` iteratively parses values from the input `Reader`.
 pub struct Read<R: Reader> {
-    chars: RefCell<Peekable<CharReader<R>>>,
+    chars: RefCell<CharReader<R>>,
+    /// Characters that have been peeked at, but not yet taken from the input.
+    lookahead: RefCell<VecDeque<char>>,
     current_location: Location,
     result: Result<(), String>,
     heap_ptr: *mut Heap,
-    had_error: bool
+    had_error: bool,
+    /// Whether to recover from failures and keep reading, rather than stopping
+    /// at the first failure.
+    recover: bool,
+    /// Whether we have recovered from a failure, but have not yet skipped
+    /// ahead to the next top level form.
+    needs_resync: bool,
+    /// The values of the datum labels, e.g. `#0=`, defined so far within the
+    /// current top level datum.
+    labels: HashMap<u64, RootedValue>,
+    /// The diagnostics for each failure reading values.
+    diagnostics: Vec<Diagnostic>,
+    /// The number of lists we are in the middle of reading.
+    open_lists: usize,
+    /// If the last failure was reaching the end of input in the middle of a
+    /// datum, the number of lists that were left open.
+    incomplete: Option<usize>,
+    /// Whether identifiers and character names are case folded, as set by the
+    /// `#!fold-case` and `#!no-fold-case` directives.
+    fold_case: bool,
 }
 
 impl<'a, R: Reader> Read<R> {
     /// Create a new `Read` instance from the given `Reader` input source.
     pub fn new(reader: R, heap: *mut Heap, file_name: String) -> Read<R> {
         Read {
-            chars: RefCell::new(CharReader::new(reader).peekable()),
+            chars: RefCell::new(CharReader::new(reader)),
+            lookahead: RefCell::new(VecDeque::new()),
             current_location: Location::new(file_name),
             result: Ok(()),
             heap_ptr: heap,
             had_error: false,
+            recover: false,
+            needs_resync: false,
+            labels: HashMap::new(),
+            diagnostics: vec!(),
+            open_lists: 0,
+            incomplete: None,
+            fold_case: false,
         }
     }
 
+    /// Enable error recovery: rather than stopping at the first failure, skip
+    /// ahead to the next `(` in the first column, which most likely begins the
+    /// next top level form, and keep reading. Each failure is still yielded as
+    /// an `Err` and recorded in `diagnostics`.
+    pub fn with_error_recovery(mut self) -> Read<R> {
+        self.recover = true;
+        self
+    }
+
+    /// Read all of the remaining input, recovering from failures, and return
+    /// the location and message of every failure.
+    pub fn syntax_errors(self) -> Vec<(Location, String)> {
+        let mut reader = self.with_error_recovery();
+        for _ in reader.by_ref() { }
+        reader.diagnostics.into_iter()
+            .map(|d| (d.location, d.message))
+            .collect()
+    }
+
+    /// If the last failure was reaching the end of input in the middle of a
+    /// datum, rather than malformed input, return the number of lists that were
+    /// left open. This lets interactive callers wait for more input.
+    pub fn incomplete(&self) -> Option<usize> {
+        self.incomplete
+    }
+
+    /// Get the location of the next character in the input.
+    pub fn current_location(&'a self) -> &'a Location {
+        &self.current_location
+    }
+
+    /// Get the characters that have been taken from the input to peek at, but
+    /// have not been read yet, in order. When reading a single datum from a
+    /// port, these must be given back to the port afterwards.
+    pub fn unread_chars(&self) -> Vec<char> {
+        self.lookahead.borrow().iter().map(|c| *c).collect()
+    }
+
     /// Get the current context.
     fn heap(&'a self) -> &'a mut Heap {
         unsafe {
@@ -173,15 +464,28 @@ impl<'a, R: Reader> Read<R> {
 
     /// Peek at the next character in our input stream.
     fn peek_char(&self) -> Option<char> {
-        match self.chars.borrow_mut().peek() {
-            None    => None,
-            Some(c) => Some(*c)
+        self.peek_nth_char(0)
+    }
+
+    /// Peek at the character `n` characters ahead in our input stream, where
+    /// `peek_nth_char(0)` is the next character.
+    fn peek_nth_char(&self, n: usize) -> Option<char> {
+        let mut lookahead = self.lookahead.borrow_mut();
+        while lookahead.len() <= n {
+            match self.chars.borrow_mut().next() {
+                None    => return None,
+                Some(c) => lookahead.push_back(c),
+            }
         }
+        Some(lookahead[n])
     }
 
     /// Take the next character from the input stream.
     fn next_char(&mut self) -> Option<char> {
-        let opt_c = self.chars.borrow_mut().next();
+        let opt_c = match self.lookahead.borrow_mut().pop_front() {
+            None => self.chars.borrow_mut().next(),
+            c    => c,
+        };
 
         if let Some(ref c) = opt_c.as_ref() {
             match **c {
@@ -191,11 +495,30 @@ impl<'a, R: Reader> Read<R> {
                 },
                 _ => self.current_location.column += 1,
             };
+            self.current_location.offset += c.len_utf8() as u64;
+
+            self.current_location.end_line = self.current_location.line;
+            self.current_location.end_column = self.current_location.column;
+            self.current_location.end_offset = self.current_location.offset;
         }
 
         opt_c
     }
 
+    /// Skip to the next `(` in the first column, after recovering from a
+    /// failure.
+    fn resync(&mut self) {
+        self.needs_resync = false;
+        loop {
+            match self.peek_char() {
+                None                                           => return,
+                Some('(') if self.current_location.column == 1 => return,
+                _                                              => { },
+            }
+            self.next_char();
+        }
+    }
+
     /// Skip to after the next newline character.
     fn skip_line(&mut self) {
         loop {
@@ -208,19 +531,117 @@ impl<'a, R: Reader> Read<R> {
         }
     }
 
-    /// Trim initial whitespace and skip comments.
-    fn trim(&mut self) {
+    /// Skip a nestable block comment, after the leading `#|` has already been
+    /// taken from the input. If this ever returns `Some`, then it will always be
+    /// `Some((Location, Err))`.
+    fn skip_block_comment(&mut self) -> Option<SchemeResultAndLocation> {
+        let mut depth = 1;
+        while depth > 0 {
+            match [self.next_char(), self.peek_char()] {
+                [None, _]              => {
+                    let diagnostic = Diagnostic::new(self.current_location.clone(),
+                                                     "Unterminated block comment".to_string());
+                    return self.report_incomplete(diagnostic);
+                },
+                [Some('|'), Some('#')] => {
+                    self.next_char();
+                    depth -= 1;
+                },
+                [Some('#'), Some('|')] => {
+                    self.next_char();
+                    depth += 1;
+                },
+                _                      => { },
+            }
+        }
+        None
+    }
+
+    /// Skip the next datum, after the leading `#;` of a datum comment has
+    /// already been taken from the input. If this ever returns `Some`, then it
+    /// will always be `Some((Location, Err))`.
+    fn skip_datum(&mut self) -> Option<SchemeResultAndLocation> {
+        match self.read_datum() {
+            Some((_, Ok(_))) => None,
+            None             => self.unexpected_eof(),
+            err              => err,
+        }
+    }
+
+    /// Read a `#!fold-case` or `#!no-fold-case` directive, which are treated
+    /// like comments except for changing whether subsequent identifiers are
+    /// case folded.
+    fn read_directive(&mut self) -> Option<SchemeResultAndLocation> {
+        let location = self.current_location.clone();
+        self.next_char();
+        self.next_char();
+
+        let mut name = String::new();
         loop {
-            let skip_line = match self.peek_char() {
-                Some(c) if c.is_whitespace() => false,
-                Some(c) if is_comment(&c)    => true,
-                _                            => return,
-            };
+            match self.peek_char() {
+                Some(c) if !is_delimiter(&c) => {
+                    self.next_char();
+                    name.push(c);
+                },
+                _                            => break,
+            }
+        }
 
-            if skip_line {
-                self.skip_line();
-            } else {
-                self.next_char();
+        match name.as_slice() {
+            "fold-case"    => self.fold_case = true,
+            "no-fold-case" => self.fold_case = false,
+            _              => return self.report_failure_at(
+                location, format!("Unknown directive: #!{}", name)),
+        };
+        None
+    }
+
+    /// Return true if we are at the start of a `#!/usr/bin/env oxischeme`
+    /// style interpreter line at the very beginning of the input, which is
+    /// ignored so that scripts can be directly executable. This is only
+    /// distinguished from a directive by the `/` or space after the `#!`.
+    fn at_shebang(&self) -> bool {
+        self.current_location.offset == 0 && match self.peek_nth_char(2) {
+            Some('/') | Some(' ') => true,
+            _                     => false,
+        }
+    }
+
+    /// Trim initial whitespace and skip line comments, block comments, and
+    /// datum comments. If this ever returns `Some`, then it will always be
+    /// `Some((Location, Err))`.
+    fn trim(&mut self) -> Option<SchemeResultAndLocation> {
+        loop {
+            match [self.peek_char(), self.peek_nth_char(1)] {
+                [Some(c), _] if c.is_whitespace() => {
+                    self.next_char();
+                },
+                [Some(c), _] if is_comment(&c)    => {
+                    self.skip_line();
+                },
+                [Some('#'), Some('|')]            => {
+                    self.next_char();
+                    self.next_char();
+                    if let Some(e) = self.skip_block_comment() {
+                        return Some(e);
+                    }
+                },
+                [Some('#'), Some(';')]            => {
+                    self.next_char();
+                    self.next_char();
+                    if let Some(e) = self.skip_datum() {
+                        return Some(e);
+                    }
+                },
+                [Some('#'), Some('!')] if self.at_shebang() => {
+                    self.skip_line();
+                },
+                [Some('#'), Some('!')]            => {
+                    if let Some(e) = self.read_directive() {
+                        return Some(e);
+                    }
+                },
+                _                                 => return None,
             }
         }
     }
@@ -233,9 +654,43 @@ impl<'a, R: Reader> Read<R> {
 
     /// Report a failure reading values.
     fn report_failure(&mut self, msg: String) -> Option<SchemeResultAndLocation> {
-        self.had_error = true;
-        Some((self.current_location.clone(),
-             Err(format!("{}: {}", self.current_location, msg))))
+        let location = self.current_location.clone();
+        self.report_failure_at(location, msg)
+    }
+
+    /// Report a failure reading values that originates at the given location,
+    /// rather than the current location.
+    fn report_failure_at(&mut self,
+                         location: Location,
+                         msg: String) -> Option<SchemeResultAndLocation> {
+        self.report_diagnostic(Diagnostic::new(location, msg))
+    }
+
+    /// Report a failure reading values, described by the given diagnostic.
+    fn report_diagnostic(&mut self,
+                         diagnostic: Diagnostic) -> Option<SchemeResultAndLocation> {
+        if self.recover {
+            self.needs_resync = true;
+        } else {
+            self.had_error = true;
+        }
+        let result = Some((diagnostic.location.clone(),
+                           Err(format!("{}", diagnostic))));
+        self.diagnostics.push(diagnostic);
+        result
+    }
+
+    /// Report a failure to read a datum because the input ended in the middle
+    /// of it.
+    fn report_incomplete(&mut self,
+                         diagnostic: Diagnostic) -> Option<SchemeResultAndLocation> {
+        self.incomplete = Some(self.open_lists);
+        self.report_diagnostic(diagnostic)
+    }
+
+    /// Get the diagnostics for each failure reading values thus far.
+    pub fn diagnostics(&'a self) -> &'a [Diagnostic] {
+        self.diagnostics.as_slice()
     }
 
     /// Report an unexpected character.
@@ -249,7 +704,9 @@ impl<'a, R: Reader> Read<R> {
     fn expect_character(&mut self, c: char) -> Option<SchemeResultAndLocation> {
         match self.next_char() {
             None => {
-                self.report_failure(format!("Expected '{}', but found EOF.", c))
+                let diagnostic = Diagnostic::new(self.current_location.clone(),
+                                                 format!("Expected '{}', but found EOF.", c));
+                self.report_incomplete(diagnostic)
             },
             Some(d) if d != c => {
                 self.report_failure(format!("Expected '{}', found: '{}'", c, d))
@@ -260,17 +717,26 @@ impl<'a, R: Reader> Read<R> {
 
     /// Report an unexpected EOF.
     fn unexpected_eof(&mut self) -> Option<SchemeResultAndLocation> {
-        self.report_failure("Unexpected EOF".to_string())
+        let diagnostic = Diagnostic::new(self.current_location.clone(),
+                                         "Unexpected EOF".to_string());
+        self.report_incomplete(diagnostic)
     }
 
     /// Report a bad character literal, e.g. `#\bad`.
-    fn bad_character_literal(&mut self) -> Option<SchemeResultAndLocation> {
-        self.report_failure("Bad character value".to_string())
+    fn bad_character_literal(&mut self, name: String) -> Option<SchemeResultAndLocation> {
+        self.report_failure(format!("Bad character value: #\\{}", name))
     }
 
-    /// Report an unterminated string literal.
-    fn unterminated_string(&mut self) -> Option<SchemeResultAndLocation> {
-        self.report_failure("Unterminated string literal".to_string())
+    /// Report an unterminated string or `|symbol|` literal, depending on the
+    /// `delimiter` that surrounds it, which started at the given location.
+    fn unterminated(&mut self,
+                    delimiter: char,
+                    start: &Location) -> Option<SchemeResultAndLocation> {
+        let kind = literal_kind(delimiter);
+        let diagnostic = Diagnostic::new(self.current_location.clone(),
+                                         format!("Unterminated {} literal", kind))
+            .with_note(start.clone(), format!("the {} literal starts here", kind));
+        self.report_incomplete(diagnostic)
     }
 
     /// Register the given value as having originated form the given location,
@@ -278,10 +744,19 @@ impl<'a, R: Reader> Read<R> {
     fn enlocate(&self,
                 location: Location,
                 val: RootedValue) -> Option<SchemeResultAndLocation> {
+        let span = location.to(&self.current_location);
         if let Some(pair) = val.to_pair(self.heap()) {
-            self.heap().enlocate(location.clone(), pair);
+            self.heap().enlocate(span.clone(), pair);
         }
-        Some((location, Ok(val)))
+        Some((span, Ok(val)))
+    }
+
+    /// Register the datum in the car of the given pair as having originated
+    /// from the given location. This gives every datum within a list a
+    /// location, even atoms.
+    fn enlocate_car(&self, location: Location, pair: &RootedValue) {
+        let cons = pair.to_pair(self.heap()).expect("Should be passed a pair");
+        self.heap().enlocate_car(location, cons);
     }
 
     /// Given a value, root it and wrap it for returning from the iterator.
@@ -292,73 +767,52 @@ impl<'a, R: Reader> Read<R> {
     /// Read a character value, after the starting '#' and '\' characters have
     /// already been eaten.
     fn read_character(&mut self, loc: Location) -> Option<SchemeResultAndLocation> {
-        match [self.next_char(), self.peek_char()] {
-            // Normal character, e.g. `#\f`.
-            [Some(c), d] if is_eof_or_delimiter(&d) => {
-                self.root(loc, Value::new_character(c))
-            },
+        let first = match self.next_char() {
+            None    => return self.unexpected_eof(),
+            Some(c) => c,
+        };
 
-            // Newline character: `#\newline`.
-            [Some('n'), Some('e')] => match [self.next_char(),
-                                             self.next_char(),
-                                             self.next_char(),
-                                             self.next_char(),
-                                             self.next_char(),
-                                             self.next_char(),
-                                             self.peek_char()] {
-                [Some('e'),
-                 Some('w'),
-                 Some('l'),
-                 Some('i'),
-                 Some('n'),
-                 Some('e'),
-                 d] if is_eof_or_delimiter(&d) => {
-                    self.root(loc, Value::new_character('\n'))
-                },
-                _                              => self.bad_character_literal(),
-            },
+        // Normal character, e.g. `#\f`.
+        if is_eof_or_delimiter(&self.peek_char()) {
+            return self.root(loc, Value::new_character(first));
+        }
 
-            // Space character: `#\space`.
-            [Some('s'), Some('p')] => match [self.next_char(),
-                                             self.next_char(),
-                                             self.next_char(),
-                                             self.next_char(),
-                                             self.peek_char()] {
-                [Some('p'),
-                 Some('a'),
-                 Some('c'),
-                 Some('e'),
-                 d] if is_eof_or_delimiter(&d) => {
-                    self.root(loc, Value::new_character(' '))
-                },
-                _                              => self.bad_character_literal(),
-            },
+        // Otherwise, this is either a named character, e.g. `#\newline`, or a
+        // hex scalar value, e.g. `#\x41`.
+        let mut name = String::new();
+        name.push(first);
+        while !is_eof_or_delimiter(&self.peek_char()) {
+            name.push(self.next_char().expect("We just peeked a character"));
+        }
 
-            // Tab character: `#\tab`.
-            [Some('t'), Some('a')] => match [self.next_char(),
-                                             self.next_char(),
-                                             self.peek_char()] {
-                [Some('a'),
-                 Some('b'),
-                 d] if is_eof_or_delimiter(&d) => {
-                    self.root(loc, Value::new_character('\t'))
-                },
-                _                              => self.bad_character_literal(),
-            },
+        let folded = if self.fold_case {
+            name.chars().map(|c| c.to_lowercase()).collect()
+        } else {
+            name.clone()
+        };
+        if let Some(c) = named_character(folded.as_slice()) {
+            return self.root(loc, Value::new_character(c));
+        }
 
-            _ => self.bad_character_literal(),
+        if first == 'x' {
+            if let Some(c) = hex_scalar_value(name.slice_from(1)) {
+                return self.root(loc, Value::new_character(c));
+            }
         }
+
+        self.bad_character_literal(name)
     }
 
     /// Given that we have already peeked a '#' character, read in either a
-    /// boolean or a character.
-    fn read_bool_or_char(&mut self,
-                         loc: Location) -> Option<SchemeResultAndLocation> {
+    /// boolean, a character, a number with radix or exactness prefixes, or a
+    /// datum label.
+    fn read_hash(&mut self, loc: Location) -> Option<SchemeResultAndLocation> {
         if let Some(e) = self.expect_character('#') {
             return Some(e);
         }
 
-        // Deterimine if this is a boolean or a character.
+        // Deterimine if this is a boolean, a character, a number, or a datum
+        // label.
         match [self.next_char(), self.peek_char()] {
             [Some('t'), d] if is_eof_or_delimiter(&d)  => {
                 self.root(loc, Value::new_boolean(true))
@@ -369,45 +823,233 @@ impl<'a, R: Reader> Read<R> {
             [Some('\\'), _]                            => {
                 self.read_character(loc)
             },
+            [Some(c), _] if is_number_prefix(&c)       => {
+                self.read_prefixed_number(c, loc)
+            },
+            [Some(c), _] if c.is_digit(10)             => {
+                self.read_label(c, loc)
+            },
             [Some(c), _]                               => {
                 self.unexpected_character(&c)
             },
-            _                                          => None,
+            [None, _]                                  => self.unexpected_eof(),
         }
     }
 
-    /// Read an integer.
-    fn read_integer(&mut self,
-                    is_negative: bool,
-                    loc: Location) -> Option<SchemeResultAndLocation> {
-        let sign : i64 = if is_negative { -1 } else { 1 };
+    /// Read a datum label definition, e.g. `#0=(a b . #0#)`, or a reference to
+    /// a previously defined datum label, e.g. `#0#`, after the leading '#' and
+    /// `first` digit have already been taken from the input.
+    fn read_label(&mut self,
+                  first: char,
+                  loc: Location) -> Option<SchemeResultAndLocation> {
+        let mut label = first.to_digit(10).expect("Should be passed a digit") as u64;
 
-        let mut abs_value : i64 = match self.next_char() {
-            None    => return self.unexpected_eof(),
-            Some(c) => match c.to_digit(10) {
-                None    => return self.unexpected_character(&c),
-                Some(d) => d as i64
+        loop {
+            match self.next_char() {
+                Some('=')                 => return self.read_labeled_datum(label, loc),
+                Some('#')                 => {
+                    // Don't register the location of the reference, since it
+                    // would clobber the location of the labeled datum.
+                    return match self.labels.get(&label).map(|v| **v) {
+                        Some(val) => Some((loc.to(&self.current_location),
+                                           Ok(Rooted::new(self.heap(), val)))),
+                        None      => self.report_failure_at(
+                            loc, format!("Undefined datum label: #{}#", label)),
+                    };
+                },
+                Some(c) if c.is_digit(10) => {
+                    label = match label.checked_mul(10).and_then(|l| {
+                        l.checked_add(c.to_digit(10).unwrap() as u64)
+                    }) {
+                        Some(l) => l,
+                        None    => return self.report_failure_at(
+                            loc, "Datum label is too large".to_string()),
+                    };
+                },
+                Some(c)                   => return self.unexpected_character(&c),
+                None                      => return self.unexpected_eof(),
             }
+        }
+    }
+
+    /// Read the datum labeled by a `#n=` datum label definition. References to
+    /// the label within the datum itself refer to the datum, which allows
+    /// reading cyclic structures.
+    fn read_labeled_datum(&mut self,
+                          label: u64,
+                          loc: Location) -> Option<SchemeResultAndLocation> {
+        // Until we have read the datum, references to its label refer to a
+        // unique placeholder pair, which we then replace with the datum.
+        let empty = Rooted::new(self.heap(), Value::EmptyList);
+        let placeholder = Value::new_pair(self.heap(), &empty, &empty);
+        self.labels.insert(label, placeholder.clone());
+
+        let val = match self.read_datum() {
+            Some((_, Ok(v))) => v,
+            None             => return self.unexpected_eof(),
+            err              => return err,
         };
 
+        if val == placeholder {
+            return self.report_failure_at(
+                loc, format!("Datum label cannot label itself: #{}=", label));
+        }
+
+        replace_placeholder(self.heap(), &val, &placeholder);
+        self.labels.insert(label, val.clone());
+        Some((loc.to(&self.current_location), Ok(val)))
+    }
+
+    /// Read a number with one or more prefixes, e.g. `#x1F` or `#e#x10`, after
+    /// the first prefix's '#' and `first` prefix character have already been
+    /// taken from the input.
+    fn read_prefixed_number(&mut self,
+                            first: char,
+                            loc: Location) -> Option<SchemeResultAndLocation> {
+        let mut radix = None;
+        let mut exactness = None;
+        let mut prefix = Some(first);
+
         loop {
-            match self.peek_char() {
-                None                        => break,
-                Some(c) if is_delimiter(&c) => break,
-                Some(c)                     => match c.to_digit(10) {
-                    None    => return self.unexpected_character(&c),
-                    Some(d) => abs_value = (abs_value * 10) + (d as i64),
-                }
+            match prefix.map(|c| c.to_lowercase()) {
+                Some('x') if radix.is_none()     => radix = Some(16),
+                Some('o') if radix.is_none()     => radix = Some(8),
+                Some('b') if radix.is_none()     => radix = Some(2),
+                Some('d') if radix.is_none()     => radix = Some(10),
+                Some('e') if exactness.is_none() => exactness = Some(true),
+                Some('i') if exactness.is_none() => exactness = Some(false),
+                Some(c)                          => {
+                    return self.report_failure(
+                        format!("Bad number prefix: #{}", c));
+                },
+                None                             => return self.unexpected_eof(),
+            }
+
+            if self.peek_char() != Some('#') {
+                break;
             }
             self.next_char();
+            prefix = self.next_char();
         }
 
-        self.root(loc, Value::new_integer(abs_value * sign))
+        self.read_number(radix.unwrap_or(10), exactness, loc)
+    }
+
+    /// Report an unexpected character in a number literal.
+    fn bad_number_character(&mut self, c: &char) -> Option<SchemeResultAndLocation> {
+        self.report_failure(format!("Unexpected character in number: '{}'", c))
+    }
+
+    /// Read a number in the given radix, after any prefixes have already been
+    /// taken from the input. The `exactness` is `Some(true)` for an `#e`
+    /// prefix, `Some(false)` for an `#i` prefix, and `None` otherwise.
+    ///
+    /// Decimal points and exponents are accepted in base 10, and make the
+    /// number inexact unless it has an `#e` prefix. Exact numbers must be
+    /// integral, since we don't have rationals.
+    fn read_number(&mut self,
+                   radix: u32,
+                   exactness: Option<bool>,
+                   loc: Location) -> Option<SchemeResultAndLocation> {
+        let is_negative = match self.peek_char() {
+            Some('-') => { self.next_char(); true },
+            Some('+') => { self.next_char(); false },
+            _         => false,
+        };
+
+        // The mantissa's digits, ignoring any decimal point.
+        let mut digits = vec!();
+        // The number of digits after the decimal point.
+        let mut scale : i64 = 0;
+        let mut saw_point = false;
+        let mut saw_exponent = false;
+        let is_decimal = radix == 10;
+
+        loop {
+            let c = match self.peek_char() {
+                Some(c) if !is_delimiter(&c) => c,
+                _                            => break,
+            };
+
+            match c.to_digit(radix) {
+                Some(d) => {
+                    digits.push(d as i64);
+                    if saw_point {
+                        scale += 1;
+                    }
+                },
+                None if is_decimal && c == '.' && !saw_point => {
+                    saw_point = true;
+                },
+                None if is_decimal && is_exponent_marker(&c) && !digits.is_empty() => {
+                    self.next_char();
+                    saw_exponent = true;
+                    break;
+                },
+                None => {
+                    return self.bad_number_character(&c);
+                },
+            }
+            self.next_char();
+        }
+
+        if digits.is_empty() {
+            return self.report_failure_at(loc, "Expected digits in number".to_string());
+        }
+
+        let mut exponent : i64 = 0;
+        if saw_exponent {
+            let exponent_is_negative = match self.peek_char() {
+                Some('-') => { self.next_char(); true },
+                Some('+') => { self.next_char(); false },
+                _         => false,
+            };
+
+            let mut saw_exponent_digit = false;
+            loop {
+                let c = match self.peek_char() {
+                    Some(c) if !is_delimiter(&c) => c,
+                    _                            => break,
+                };
+
+                match c.to_digit(10).and_then(|d| {
+                    exponent.checked_mul(10).and_then(|e| e.checked_add(d as i64))
+                }) {
+                    Some(e) => exponent = e,
+                    None    => return self.bad_number_character(&c),
+                }
+                saw_exponent_digit = true;
+                self.next_char();
+            }
+
+            if !saw_exponent_digit {
+                return self.report_failure("Expected digits in exponent".to_string());
+            }
+
+            if exponent_is_negative {
+                exponent = -exponent;
+            }
+        }
+
+        if !exactness.unwrap_or(!saw_point && !saw_exponent) {
+            let value = inexact_real(digits.as_slice(), radix, exponent - scale);
+            return self.root(loc, Value::new_float(if is_negative { -value } else { value }));
+        }
+
+        match exact_integer(digits.as_slice(), radix, exponent - scale) {
+            Ok(abs_value) => {
+                let value = if is_negative { -abs_value } else { abs_value };
+                self.root(loc, Value::new_integer(value))
+            },
+            Err(msg)      => self.report_failure_at(loc, msg),
+        }
     }
 
     /// Read a pair, with the leading '(' already taken from the input.
     fn read_pair(&mut self, loc: Location) -> Option<SchemeResultAndLocation> {
-        self.trim();
+        if let Some(e) = self.trim() {
+            return Some(e);
+        }
         match self.peek_char() {
             None      => return self.unexpected_eof(),
 
@@ -417,12 +1059,14 @@ impl<'a, R: Reader> Read<R> {
             },
 
             _         => {
-                let car = match self.next() {
-                    Some((_, Ok(v))) => v,
+                let (car_loc, car) = match self.read_datum() {
+                    Some((car_loc, Ok(v))) => (car_loc, v),
                     err => return err,
                 };
 
-                self.trim();
+                if let Some(e) = self.trim() {
+                    return Some(e);
+                }
                 let next_loc = self.current_location.clone();
 
                 match self.peek_char() {
@@ -431,19 +1075,21 @@ impl<'a, R: Reader> Read<R> {
                     // Improper list.
                     Some('.') => {
                         self.next_char();
-                        let cdr = match self.next() {
+                        let cdr = match self.read_datum() {
                             Some((_, Ok(v))) => v,
                             err => return err,
                         };
 
-                        self.trim();
+                        if let Some(e) = self.trim() {
+                            return Some(e);
+                        }
                         if let Some(e) = self.expect_character(')') {
                             return Some(e);
                         }
 
-                        return self.enlocate(loc, Value::new_pair(self.heap(),
-                                                                  &car,
-                                                                  &cdr));
+                        let pair = Value::new_pair(self.heap(), &car, &cdr);
+                        self.enlocate_car(car_loc, &pair);
+                        return self.enlocate(loc, pair);
                     },
 
                     // Proper list.
@@ -453,9 +1099,9 @@ impl<'a, R: Reader> Read<R> {
                             err => return err,
                         };
 
-                        return self.enlocate(loc, Value::new_pair(self.heap(),
-                                                                  &car,
-                                                                  &cdr));
+                        let pair = Value::new_pair(self.heap(), &car, &cdr);
+                        self.enlocate_car(car_loc, &pair);
+                        return self.enlocate(loc, pair);
                     },
                 };
             },
@@ -472,17 +1118,12 @@ impl<'a, R: Reader> Read<R> {
 
         loop {
             match self.next_char() {
-                None       => return self.unterminated_string(),
+                None       => return self.unterminated('"', &loc),
                 Some('"')  => return self.enlocate(loc, Value::new_string(self.heap(),
                                                                           str)),
                 Some('\\') => {
-                    match self.next_char() {
-                        Some('n')  => str.push('\n'),
-                        Some('t')  => str.push('\t'),
-                        Some('\\') => str.push('\\'),
-                        Some('"')  => str.push('"'),
-                        Some(c)    => return self.unexpected_character(&c),
-                        None       => return self.unterminated_string(),
+                    if let Some(e) = self.read_escape(&mut str, '"', &loc) {
+                        return Some(e);
                     }
                 },
                 Some(c)    => str.push(c),
@@ -490,26 +1131,92 @@ impl<'a, R: Reader> Read<R> {
         }
     }
 
-    /// Read a symbol in from the input. Optionally supply a prefix character
-    /// that was already read from the symbol.
-    fn read_symbol(&mut self,
-                   prefix: Option<char>,
-                   loc: Location) -> Option<SchemeResultAndLocation> {
+    /// Read an escape sequence within a string or `|symbol|` literal, after
+    /// the '\\' has already been taken from the input, and push the character
+    /// it denotes onto `str`. The `delimiter` is the character that surrounds
+    /// the literal, and line continuations are only allowed within strings.
+    /// The `start` is the location of the beginning of the literal.
+    fn read_escape(&mut self,
+                   str: &mut String,
+                   delimiter: char,
+                   start: &Location) -> Option<SchemeResultAndLocation> {
+        match self.next_char() {
+            Some('a')  => str.push('\x07'),
+            Some('b')  => str.push('\x08'),
+            Some('t')  => str.push('\t'),
+            Some('n')  => str.push('\n'),
+            Some('r')  => str.push('\r'),
+            Some('"')  => str.push('"'),
+            Some('\\') => str.push('\\'),
+            Some('|')  => str.push('|'),
+
+            // Hex scalar value escape, e.g. `\x41;`.
+            Some('x')  => {
+                let mut digits = String::new();
+                loop {
+                    match self.next_char() {
+                        Some(';') => break,
+                        Some(c)   => digits.push(c),
+                        None      => return self.unterminated(delimiter, start),
+                    }
+                }
+
+                match hex_scalar_value(digits.as_slice()) {
+                    Some(c) => str.push(c),
+                    None    => return self.report_failure(
+                        format!("Bad hex escape in {}: \\x{};",
+                                literal_kind(delimiter),
+                                digits)),
+                }
+            },
+
+            // Line continuation: the backslash, any intraline whitespace around
+            // the line ending, and the line ending itself are all elided. The
+            // line ending may be `\n`, `\r\n`, or a lone `\r`.
+            Some(c) if delimiter == '"' && (c == '\n' || c == '\r' || is_intraline_whitespace(&c)) => {
+                let mut saw_newline = false;
+                let mut next = c;
+                loop {
+                    if next == '\n' || next == '\r' {
+                        saw_newline = true;
+                        if next == '\r' && self.peek_char() == Some('\n') {
+                            self.next_char();
+                        }
+                    }
+
+                    match self.peek_char() {
+                        Some('\n') | Some('\r') if !saw_newline => { },
+                        Some(d) if is_intraline_whitespace(&d)   => { },
+                        _                                        => break,
+                    }
+                    next = self.next_char().expect("Just peeked a character");
+                }
+
+                if !saw_newline {
+                    return self.report_failure(
+                        "Expected a newline after '\\' in string".to_string());
+                }
+            },
+
+            Some(c)    => return self.unexpected_character(&c),
+            None       => return self.unterminated(delimiter, start),
+        };
+        None
+    }
+
+    /// Read a symbol in from the input.
+    fn read_symbol(&mut self, loc: Location) -> Option<SchemeResultAndLocation> {
         let mut str = String::new();
 
-        if prefix.is_some() {
-            str.push(prefix.unwrap());
-        } else {
-            match self.next_char() {
-                Some(c) if is_symbol_initial(&c) => str.push(c),
-                Some(c)                          => {
-                    return self.unexpected_character(&c);
-                },
-                None                             => {
-                    return self.unexpected_eof();
-                },
-            };
-        }
+        match self.next_char() {
+            Some(c) if is_symbol_initial(&c) => str.push(c),
+            Some(c)                          => {
+                return self.unexpected_character(&c);
+            },
+            None                             => {
+                return self.unexpected_eof();
+            },
+        };
 
         loop {
             match self.peek_char() {
@@ -521,7 +1228,69 @@ impl<'a, R: Reader> Read<R> {
             };
         }
 
-        return self.enlocate(loc, self.heap().get_or_create_symbol(str));
+        if self.fold_case {
+            str = str.chars().map(|c| c.to_lowercase()).collect();
+        }
+
+        if let Some(x) = special_float(str.as_slice()) {
+            return self.root(loc, Value::new_float(x));
+        }
+
+        return self.enlocate(loc, self.heap().get_or_create_symbol(str));
+    }
+
+    /// Read a symbol delimited by vertical bars, e.g. `|hello world|`, which
+    /// may contain any characters as well as escapes. These symbols are never
+    /// case folded.
+    fn read_delimited_symbol(&mut self, loc: Location) -> Option<SchemeResultAndLocation> {
+        if let Some(e) = self.expect_character('|') {
+            return Some(e);
+        }
+
+        let mut str = String::new();
+
+        loop {
+            match self.next_char() {
+                None       => return self.unterminated('|', &loc),
+                Some('|')  => return self.enlocate(
+                    loc, self.heap().get_or_create_symbol(str)),
+                Some('\\') => {
+                    if let Some(e) = self.read_escape(&mut str, '|', &loc) {
+                        return Some(e);
+                    }
+                },
+                Some(c)    => str.push(c),
+            }
+        }
+    }
+
+    /// Read the next datum from the input.
+    fn read_datum(&mut self) -> Option<SchemeResultAndLocation> {
+        if let Some(e) = self.trim() {
+            return Some(e);
+        }
+
+        let location = self.current_location.clone();
+
+        match self.peek_char() {
+            None                             => None,
+            Some('\'')                       => self.read_quoted(location),
+            Some(c) if is_number_start(&c, &self.peek_nth_char(1)) => {
+                self.read_number(10, None, location)
+            },
+            Some('#')                        => self.read_hash(location),
+            Some('"')                        => self.read_string(location),
+            Some('|')                        => self.read_delimited_symbol(location),
+            Some('(')                        => {
+                self.next_char();
+                self.open_lists += 1;
+                let result = self.read_pair(location);
+                self.open_lists -= 1;
+                result
+            },
+            Some(c) if is_symbol_initial(&c) => self.read_symbol(location),
+            Some(c)                          => self.unexpected_character(&c),
+        }
     }
 
     /// Read a quoted form from input, e.g. `'(1 2 3)`.
@@ -530,12 +1299,13 @@ impl<'a, R: Reader> Read<R> {
             return Some(e);
         }
 
-        return match self.next() {
+        return match self.read_datum() {
             Some((_, Ok(val))) => self.enlocate(loc,
                                                 list(self.heap(), &mut [
                                                     self.heap().get_or_create_symbol("quote".to_string()),
                                                     val
                                                 ])),
+            None => self.unexpected_eof(),
             err => err
         };
     }
@@ -549,32 +1319,62 @@ impl<R: Reader> Iterator for Read<R> {
             return None;
         }
 
-        self.trim();
-        let location = self.current_location.clone();
+        if self.needs_resync {
+            self.resync();
+        }
 
-        match self.peek_char() {
-            None                             => None,
-            Some('\'')                       => self.read_quoted(location),
-            Some('-')                        => {
-                self.next_char();
-                match self.peek_char() {
-                    Some(c) if c.is_digit(10) => {
-                        self.read_integer(true, location)
-                    },
-                    _                         => self.read_symbol(Some('-'),
-                                                                  location),
-                }
-            },
-            Some(c) if c.is_digit(10)        => self.read_integer(false,
-                                                                  location),
-            Some('#')                        => self.read_bool_or_char(location),
-            Some('"')                        => self.read_string(location),
-            Some('(')                        => {
-                self.next_char();
-                self.read_pair(location)
+        // Datum labels are scoped to the top level datum they appear within.
+        self.labels.clear();
+        self.open_lists = 0;
+        self.incomplete = None;
+        let result = self.read_datum();
+        if let Some(&(_, Ok(_))) = result.as_ref() {
+            return result;
+        }
+
+        // An IO error looks like the end of input to the rest of the reader,
+        // so whatever `read_datum` made of it, report the IO error instead.
+        // Nothing more can be read after one, even when recovering from
+        // syntax errors.
+        let io_error = self.chars.borrow_mut().error.take();
+        match io_error {
+            Some(e) => {
+                let location = self.current_location.clone();
+                let result = self.report_failure_at(location,
+                                                    format!("IO error: {}", e));
+                self.had_error = true;
+                result
             },
-            Some(c) if is_symbol_initial(&c) => self.read_symbol(None, location),
-            Some(c)                          => self.unexpected_character(&c),
+            None    => result,
+        }
+    }
+}
+
+/// Replace every reference to the `placeholder` pair within `val` with `val`
+/// itself.
+fn replace_placeholder(heap: &mut Heap, val: &RootedValue, placeholder: &RootedValue) {
+    let mut seen : HashSet<ConsPtr> = HashSet::new();
+    let mut pending = vec!(val.clone());
+
+    while let Some(v) = pending.pop() {
+        if let Some(mut cons) = v.to_pair(heap) {
+            if !seen.insert(*cons) {
+                continue;
+            }
+
+            let car = cons.car(heap);
+            if car == *placeholder {
+                cons.set_car(val);
+            } else {
+                pending.push(car);
+            }
+
+            let cdr = cons.cdr(heap);
+            if cdr == *placeholder {
+                cons.set_cdr(val);
+            } else {
+                pending.push(cdr);
+            }
         }
     }
 }
@@ -605,13 +1405,25 @@ pub fn read_from_file(path_name: &str, heap: *mut Heap) -> IoResult<Read<File>>
     let file_name = path_name.clone().to_string();
     let path = Path::new(path_name);
     let file = try!(File::open(&path));
-    Ok(Read::new(file, heap, file_name))
+    let mut reader = Read::new(file, heap, file_name);
+    reader.current_location.is_file = true;
+    Ok(reader)
+}
+
+/// Read the file at `path_name`, recovering from failures, and return the
+/// location and message of every syntax error in it.
+pub fn syntax_errors_in_file(path_name: &str,
+                             heap: *mut Heap) -> IoResult<Vec<(Location, String)>> {
+    let reader = try!(read_from_file(path_name, heap));
+    Ok(reader.syntax_errors())
 }
 
 // TESTS -----------------------------------------------------------------------
 
 #[cfg(test)]
 mod tests {
+    use std::f64;
+
     use super::*;
     use heap::{Heap, Rooted};
     use value::{Value};
@@ -629,6 +1441,87 @@ mod tests {
                                  Value::new_integer(-987)))
     }
 
+    #[test]
+    fn test_read_prefixed_numbers() {
+        let input = "#x1F #X-ff #o17 #b101 #d42 #e#x10 #x#e10 +7";
+        let mut heap = Heap::new();
+        let results : Vec<Value> = read_from_str(input, &mut heap, "test_read_prefixed_numbers")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+        assert_eq!(results, vec!(Value::new_integer(31),
+                                 Value::new_integer(-255),
+                                 Value::new_integer(15),
+                                 Value::new_integer(5),
+                                 Value::new_integer(42),
+                                 Value::new_integer(16),
+                                 Value::new_integer(16),
+                                 Value::new_integer(7)))
+    }
+
+    #[test]
+    fn test_read_exact_decimals() {
+        let input = "#e1.5e1 #e1e3 #e-2.50e1 #e12e-1";
+        let mut heap = Heap::new();
+        let results : Vec<SchemeResultAndLocation> =
+            read_from_str(input, &mut heap, "test_read_exact_decimals").collect();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].1.as_ref().map(|v| **v), Ok(Value::new_integer(15)));
+        assert_eq!(results[1].1.as_ref().map(|v| **v), Ok(Value::new_integer(1000)));
+        assert_eq!(results[2].1.as_ref().map(|v| **v), Ok(Value::new_integer(-25)));
+        assert!(results[3].1.is_err());
+    }
+
+    #[test]
+    fn test_read_inexact_number() {
+        let input = "1.5 -0.25 .5 1e3 #i10 #x#iff 2.5E-1 -0.0 +inf.0 -inf.0";
+        let mut heap = Heap::new();
+        let results : Vec<Value> = read_from_str(input, &mut heap, "test_read_inexact_number")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+        assert_eq!(results, vec!(Value::new_float(1.5),
+                                 Value::new_float(-0.25),
+                                 Value::new_float(0.5),
+                                 Value::new_float(1000.0),
+                                 Value::new_float(10.0),
+                                 Value::new_float(255.0),
+                                 Value::new_float(0.25),
+                                 Value::new_float(-0.0),
+                                 Value::new_float(f64::INFINITY),
+                                 Value::new_float(f64::NEG_INFINITY)));
+    }
+
+    #[test]
+    fn test_read_nan() {
+        let mut heap = Heap::new();
+        let results : Vec<SchemeResultAndLocation> =
+            read_from_str("+nan.0", &mut heap, "test_read_nan").collect();
+        assert_eq!(results.len(), 1);
+        let x = results[0].1.as_ref().ok()
+            .and_then(|v| v.to_float())
+            .expect("Should read a float");
+        assert!(x.is_nan());
+    }
+
+    #[test]
+    fn test_print_float() {
+        assert_eq!(format!("{}", Value::new_float(1.5)), "1.5");
+        assert_eq!(format!("{}", Value::new_float(1000.0)), "1000.0");
+        assert_eq!(format!("{}", Value::new_float(-0.25)), "-0.25");
+        assert_eq!(format!("{}", Value::new_float(f64::INFINITY)), "+inf.0");
+        assert_eq!(format!("{}", Value::new_float(f64::NAN)), "+nan.0");
+    }
+
+    #[test]
+    fn test_read_bad_number() {
+        let mut heap = Heap::new();
+        let results : Vec<SchemeResultAndLocation> =
+            read_from_str("(1 12x)", &mut heap, "test_read_bad_number").collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.as_ref().err(),
+                   Some(&"test_read_bad_number:1:6: \
+                          Unexpected character in number: 'x'".to_string()));
+    }
+
     #[test]
     fn test_read_booleans() {
         let input = "#t #f";
@@ -656,6 +1549,51 @@ mod tests {
                                  Value::new_character('\n')));
     }
 
+    #[test]
+    fn test_read_named_characters() {
+        let input = "#\\alarm #\\backspace #\\delete #\\escape #\\null #\\return \
+                     #\\x41 #\\x3bb #\\x";
+        let mut heap = Heap::new();
+        let results : Vec<Value> = read_from_str(input, &mut heap, "test_read_named_characters")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+        assert_eq!(results, vec!(Value::new_character('\x07'),
+                                 Value::new_character('\x08'),
+                                 Value::new_character('\x7f'),
+                                 Value::new_character('\x1b'),
+                                 Value::new_character('\0'),
+                                 Value::new_character('\r'),
+                                 Value::new_character('A'),
+                                 Value::new_character('λ'),
+                                 Value::new_character('x')));
+    }
+
+    #[test]
+    fn test_read_bad_character() {
+        let mut heap = Heap::new();
+        let results : Vec<SchemeResultAndLocation> =
+            read_from_str("#\\bogus", &mut heap, "test_read_bad_character").collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_err());
+    }
+
+    #[test]
+    fn test_print_characters_round_trip() {
+        let input = "#\\a #\\space #\\newline #\\alarm #\\delete #\\null #\\x1 #\\λ";
+        let mut heap = Heap::new();
+        let results : Vec<Value> = read_from_str(input, &mut heap, "test_print_characters")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+
+        let printed : Vec<String> = results.iter().map(|v| format!("{}", v)).collect();
+        let reread : Vec<Value> = read_from_string(printed.connect(" "),
+                                                   &mut heap,
+                                                   "test_print_characters")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+        assert_eq!(results, reread);
+    }
+
     #[test]
     fn test_read_comments() {
         let input = "1 ;; this is a comment\n2";
@@ -669,6 +1607,67 @@ mod tests {
                                  Value::new_integer(2)));
     }
 
+    #[test]
+    fn test_read_block_comments() {
+        let input = "1 #| a #| nested |# comment |# 2 (3 #| inside |#)";
+        let heap = &mut Heap::new();
+        let results : Vec<Value> = read_from_str(input, heap, "test_read_block_comments")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], Value::new_integer(1));
+        assert_eq!(results[1], Value::new_integer(2));
+        assert_eq!(results[2].car(heap).map(|v| *v),
+                   Some(Value::new_integer(3)));
+        assert_eq!(results[2].cdr(heap).map(|v| *v),
+                   Some(Value::EmptyList));
+    }
+
+    #[test]
+    fn test_read_unterminated_block_comment() {
+        let heap = &mut Heap::new();
+        let results : Vec<SchemeResultAndLocation> =
+            read_from_str("1 #| oops", heap, "test_read_unterminated_block_comment")
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.is_err());
+    }
+
+    #[test]
+    fn test_read_datum_comments() {
+        let input = "#;(ignored (form)) 1 (2 #;3 4 #; 5) #;#;6 7 8";
+        let heap = &mut Heap::new();
+        let results : Vec<Value> = read_from_str(input, heap, "test_read_datum_comments")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], Value::new_integer(1));
+        assert_eq!(results[1].len(), Ok(2));
+        assert_eq!(results[1].car(heap).map(|v| *v),
+                   Some(Value::new_integer(2)));
+        assert_eq!(results[1].cdr(heap).expect("results[1].cdr")
+                             .car(heap).map(|v| *v),
+                   Some(Value::new_integer(4)));
+        assert_eq!(results[2], Value::new_integer(8));
+    }
+
+    #[test]
+    fn test_read_locations_after_block_comment() {
+        let input = "#| one\n\
+                     two |# foo";
+        let heap = &mut Heap::new();
+        let results : Vec<Location> = read_from_str(input, heap, "test_read_locations")
+            .map(|(loc, _)| loc)
+            .collect();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line, 2);
+        assert_eq!(results[0].column, 8);
+    }
+
     #[test]
     fn test_read_pairs() {
         let input = "() (1 2 3) (1 (2) ((3)))";
@@ -796,6 +1795,80 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_read_string_escapes() {
+        let input = "\"\\a\\b\\t\\n\\r\\\\\\|\" \"\\x41;\\x3bb;\" \"one \\   \n   two\"";
+        let heap = &mut Heap::new();
+        let results : Vec<Value> = read_from_str(input, heap, "test_read_string_escapes")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+        assert_eq!(results.len(), 3);
+
+        match results[0] {
+            Value::String(str) => assert_eq!(*str, "\x07\x08\t\n\r\\|".to_string()),
+            _                  => assert!(false),
+        }
+
+        match results[1] {
+            Value::String(str) => assert_eq!(*str, "Aλ".to_string()),
+            _                  => assert!(false),
+        }
+
+        match results[2] {
+            Value::String(str) => assert_eq!(*str, "one two".to_string()),
+            _                  => assert!(false),
+        }
+    }
+
+    #[test]
+    fn test_read_string_continuation_line_endings() {
+        let input = "\"one \\\r\n  two\" \"three \\  \r  four\"";
+        let heap = &mut Heap::new();
+        let results : Vec<Value> =
+            read_from_str(input, heap, "test_read_string_continuation_line_endings")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+        assert_eq!(results.len(), 2);
+
+        match results[0] {
+            Value::String(str) => assert_eq!(*str, "one two".to_string()),
+            _                  => assert!(false),
+        }
+
+        match results[1] {
+            Value::String(str) => assert_eq!(*str, "three four".to_string()),
+            _                  => assert!(false),
+        }
+    }
+
+    #[test]
+    fn test_read_bad_string_escape() {
+        let heap = &mut Heap::new();
+        let results : Vec<SchemeResultAndLocation> =
+            read_from_str("\"\\q\" \"\\xZZ;\"", heap, "test_read_bad_string_escape")
+            .collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_err());
+    }
+
+    #[test]
+    fn test_print_string_round_trip() {
+        let original = "quote \" backslash \\ bell \x07 nul \0 tab \t newline \n λ";
+        let heap = &mut Heap::new();
+        let val = Value::new_string(heap, original.to_string());
+        let printed = format!("{}", *val);
+
+        let results : Vec<Value> = read_from_string(printed, heap, "test_print_string")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+        assert_eq!(results.len(), 1);
+
+        match results[0] {
+            Value::String(str) => assert_eq!(*str, original.to_string()),
+            _                  => assert!(false),
+        }
+    }
+
     #[test]
     fn test_read_symbols() {
         let input = "foo + - * ? !";
@@ -920,4 +1993,216 @@ mod tests {
         assert_eq!(results[3].line, 2);
         assert_eq!(results[3].column, 17);
     }
+
+    #[test]
+    fn test_read_datum_labels() {
+        let input = "(#0=(a) #0#) #0=(1 . #0#) #1=(#1# 2)";
+        let heap = &mut Heap::new();
+        let results : Vec<Value> = read_from_str(input, heap, "test_read_datum_labels")
+            .map(|(_, r)| *r.ok().expect("Should not get a read error"))
+            .collect();
+        assert_eq!(results.len(), 3);
+
+        let shared = results[0];
+        assert_eq!(shared.car(heap).map(|v| *v),
+                   shared.cdr(heap).expect("shared.cdr").car(heap).map(|v| *v));
+
+        let cdr_cycle = results[1];
+        assert_eq!(cdr_cycle.cdr(heap).map(|v| *v), Some(cdr_cycle));
+
+        let car_cycle = results[2];
+        assert_eq!(car_cycle.car(heap).map(|v| *v), Some(car_cycle));
+    }
+
+    #[test]
+    fn test_read_undefined_datum_label() {
+        let mut heap = Heap::new();
+        let results : Vec<SchemeResultAndLocation> =
+            read_from_str("#0=(1) #0#", &mut heap, "test_read_undefined_datum_label")
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].1.as_ref().err(),
+                   Some(&"test_read_undefined_datum_label:1:8: \
+                          Undefined datum label: #0#".to_string()));
+    }
+
+    #[test]
+    fn test_read_delimited_symbols() {
+        let input = "|hello world| |a\\|b| |\\x41;\\t| || |foo|";
+        let heap = &mut Heap::new();
+        let results : Vec<String> = read_from_str(input, heap, "test_read_delimited_symbols")
+            .map(|(_, r)| {
+                let val = r.ok().expect("Should not get a read error");
+                let sym = val.to_symbol(heap).expect("Should read a symbol");
+                sym.as_slice().to_string()
+            })
+            .collect();
+        assert_eq!(results, vec!("hello world".to_string(),
+                                 "a|b".to_string(),
+                                 "A\t".to_string(),
+                                 "".to_string(),
+                                 "foo".to_string()));
+    }
+
+    #[test]
+    fn test_read_unterminated_delimited_symbol() {
+        let mut heap = Heap::new();
+        let results : Vec<SchemeResultAndLocation> =
+            read_from_str("|abc", &mut heap, "test_read_unterminated_symbol").collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.as_ref().err(),
+                   Some(&"test_read_unterminated_symbol:1:5: \
+                          Unterminated symbol literal".to_string()));
+    }
+
+    #[test]
+    fn test_read_fold_case_directives() {
+        let input = "FOO #!fold-case FOO |FOO| #\\NEWLINE #!no-fold-case FOO";
+        let heap = &mut Heap::new();
+        let results : Vec<String> = read_from_str(input, heap, "test_read_fold_case")
+            .map(|(_, r)| format!("{}", *r.ok().expect("Should not get a read error")))
+            .collect();
+        assert_eq!(results, vec!("FOO".to_string(),
+                                 "foo".to_string(),
+                                 "FOO".to_string(),
+                                 "#\\newline".to_string(),
+                                 "FOO".to_string()));
+    }
+
+    #[test]
+    fn test_read_unknown_directive() {
+        let mut heap = Heap::new();
+        let results : Vec<SchemeResultAndLocation> =
+            read_from_str("#!bogus", &mut heap, "test_read_unknown_directive").collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.as_ref().err(),
+                   Some(&"test_read_unknown_directive:1:1: \
+                          Unknown directive: #!bogus".to_string()));
+    }
+
+    #[test]
+    fn test_read_io_error() {
+        use std::old_io::{self, IoErrorKind, IoResult};
+
+        /// A `Reader` that yields some input and then fails.
+        struct FailingReader {
+            input: Vec<u8>,
+        }
+
+        impl Reader for FailingReader {
+            fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+                if self.input.is_empty() {
+                    return Err(old_io::standard_error(IoErrorKind::PermissionDenied));
+                }
+                buf[0] = self.input.remove(0);
+                Ok(1)
+            }
+        }
+
+        let heap = &mut Heap::new();
+        let reader = FailingReader { input: "foo (bar".to_string().into_bytes() };
+        let results : Vec<SchemeResultAndLocation> =
+            Read::new(reader, heap, "test_read_io_error".to_string()).collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(*results[0].1.as_ref().ok().expect("Should read foo"),
+                   *heap.get_or_create_symbol("foo".to_string()));
+        assert!(results[1].1.as_ref().err().expect("Should get an IO error")
+                .starts_with("test_read_io_error:1:9: IO error: "));
+    }
+
+    #[test]
+    fn test_read_shebang() {
+        let input = "#!/usr/bin/env oxischeme\nfoo #!fold-case BAR";
+        let heap = &mut Heap::new();
+        let results : Vec<String> = read_from_str(input, heap, "test_read_shebang")
+            .map(|(_, r)| format!("{}", *r.ok().expect("Should not get a read error")))
+            .collect();
+        assert_eq!(results, vec!("foo".to_string(), "bar".to_string()));
+    }
+
+    #[test]
+    fn test_print_symbols_round_trip() {
+        let input = "foo |hello world| |a\\|b| || |1+| |+1| + |a\\\\b| |(|";
+        let heap = &mut Heap::new();
+        let printed : Vec<String> = read_from_str(input, heap, "test_print_symbols")
+            .map(|(_, r)| format!("{}", *r.ok().expect("Should not get a read error")))
+            .collect();
+        assert_eq!(printed.connect(" "), input.to_string());
+    }
+
+    #[test]
+    fn test_read_spans() {
+        let input = "foo \"λ\" (1 (2)\n 3)";
+        let heap = &mut Heap::new();
+        let results : Vec<(Location, Value)> = read_from_str(input, heap, "test_read_spans")
+            .map(|(loc, r)| (loc, *r.ok().expect("Should not get a read error")))
+            .collect();
+        let spans : Vec<(u64, u64, u64, u64, u64, u64)> = results.iter()
+            .map(|&(ref l, _)| (l.line, l.column, l.offset,
+                                l.end_line, l.end_column, l.end_offset))
+            .collect();
+        assert_eq!(spans, vec!((1, 1, 0, 1, 4, 3),
+                               (1, 5, 4, 1, 8, 8),
+                               (1, 9, 9, 2, 4, 19)));
+
+        // Atoms within lists are located by their position in the list.
+        let list = results[2].1.to_pair(heap).expect("Should read a list");
+        let one = heap.locate_car(&list);
+        assert_eq!((one.line, one.column, one.end_line, one.end_column),
+                   (1, 10, 1, 11));
+
+        let rest = list.cdr(heap).to_pair(heap).expect("Should have a cdr");
+        let sublist = heap.locate_car(&rest);
+        assert_eq!((sublist.line, sublist.column, sublist.end_line, sublist.end_column),
+                   (1, 12, 1, 15));
+
+        let rest = rest.cdr(heap).to_pair(heap).expect("Should have a cddr");
+        let three = heap.locate_car(&rest);
+        assert_eq!((three.line, three.column, three.offset, three.end_offset),
+                   (2, 2, 17, 18));
+    }
+
+    #[test]
+    fn test_read_diagnostics() {
+        let heap = &mut Heap::new();
+        let mut reader = read_from_str("(1 \"abc", heap, "test_read_diagnostics");
+        let results : Vec<SchemeResultAndLocation> = reader.by_ref().collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_err());
+
+        let diagnostics = reader.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unterminated string literal".to_string());
+        assert_eq!((diagnostics[0].location.line, diagnostics[0].location.column),
+                   (1, 8));
+        assert_eq!(diagnostics[0].notes.len(), 1);
+        assert_eq!((diagnostics[0].notes[0].0.line, diagnostics[0].notes[0].0.column),
+                   (1, 4));
+    }
+
+    #[test]
+    fn test_read_error_recovery() {
+        let heap = &mut Heap::new();
+        let input = "(1 2)\n(3 #\\bogus)\n(4 5)\n  (6 ]) 7\n(8)";
+        let results : Vec<bool> = read_from_str(input, heap, "test_read_error_recovery")
+            .with_error_recovery()
+            .map(|(_, r)| r.is_ok())
+            .collect();
+        assert_eq!(results, vec!(true, false, true, false, true));
+    }
+
+    #[test]
+    fn test_syntax_errors_in_file() {
+        let heap = &mut Heap::new();
+        let errors = syntax_errors_in_file("./tests/test_read_syntax_errors.scm", heap)
+            .ok()
+            .expect("Should be able to read the file");
+        let errors : Vec<(u64, String)> = errors.into_iter()
+            .map(|(loc, msg)| (loc.line, msg))
+            .collect();
+        assert_eq!(errors,
+                   vec!((3, "Bad character value: #\\bogus".to_string()),
+                        (7, "Expected ')', found: '4'".to_string()),
+                        (9, "Unexpected character in number: 'x'".to_string())));
+    }
 }
@@ -0,0 +1,158 @@
+// Copyright 2015 Nick Fitzgerald
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Records, as defined with `define-record-type`.
+//!
+//! Both record types and their instances are `Record`s allocated on the heap. A
+//! record type has a name and field names, and an instance has a pointer to
+//! its record type and a value for each field:
+//!
+//!     (define-record-type <point> (make-point x y) point?
+//!       (x point-x set-point-x!)
+//!       (y point-y))
+//!
+//!     (make-point 1 2) ;; => #<record point x: 1 y: 2>
+
+use std::default::{Default};
+use std::hash;
+
+use heap::{ArenaPtr, GcThing, IterGcThing, Rooted, ToGcThing, Trace};
+use value::{Value};
+
+/// A record type, or an instance of one.
+pub struct Record {
+    /// The record type this record is an instance of, or `None` if this record
+    /// is itself a record type.
+    record_type: Option<RecordPtr>,
+    /// For record types, the type's name.
+    name: String,
+    /// For record types, the names of the fields of its instances.
+    field_names: Vec<String>,
+    /// For instances, the value of each field.
+    fields: Vec<Value>,
+}
+
+impl Record {
+    /// Create a new record type with the given name and field names.
+    pub fn new_type(name: String, field_names: Vec<String>) -> Record {
+        Record {
+            record_type: None,
+            name: name,
+            field_names: field_names,
+            fields: vec!(),
+        }
+    }
+
+    /// Create a new instance of the given record type, with the given field
+    /// values.
+    pub fn new_instance(record_type: RecordPtr, fields: Vec<Value>) -> Record {
+        debug_assert!(record_type.is_type());
+        debug_assert!(record_type.field_names.len() == fields.len());
+        Record {
+            record_type: Some(record_type),
+            name: String::new(),
+            field_names: vec!(),
+            fields: fields,
+        }
+    }
+
+    /// Return true if this record is a record type, false if it is an
+    /// instance.
+    pub fn is_type(&self) -> bool {
+        self.record_type.is_none()
+    }
+
+    /// Get the record type this record is an instance of, or `None` if this
+    /// record is itself a record type.
+    pub fn record_type(&self) -> Option<RecordPtr> {
+        self.record_type
+    }
+
+    /// Get the name of this record type, or of the record type this record is
+    /// an instance of, without any surrounding angle brackets: `<point>` is
+    /// named `point`.
+    pub fn type_name(&self) -> &str {
+        let name = match self.record_type {
+            Some(ref record_type) => record_type.name.as_slice(),
+            None                  => self.name.as_slice(),
+        };
+        if name.len() > 2 && name.starts_with("<") && name.ends_with(">") {
+            name.slice(1, name.len() - 1)
+        } else {
+            name
+        }
+    }
+
+    /// Get the names of the fields of this record type's instances, or of this
+    /// instance's fields.
+    pub fn field_names(&self) -> &[String] {
+        match self.record_type {
+            Some(ref record_type) => record_type.field_names.as_slice(),
+            None                  => self.field_names.as_slice(),
+        }
+    }
+
+    /// Get the values of this instance's fields.
+    pub fn fields(&self) -> &[Value] {
+        self.fields.as_slice()
+    }
+
+    /// Get the value of the field at the given index.
+    pub fn field(&self, index: usize) -> Option<Value> {
+        self.fields.get(index).map(|val| *val)
+    }
+
+    /// Set the value of the field at the given index.
+    pub fn set_field(&mut self, index: usize, val: Value) {
+        self.fields[index] = val;
+    }
+}
+
+impl Default for Record {
+    /// Do not use this method, instead allocate records on the heap with
+    /// `Heap::allocate_record` and get back a `RecordPtr`.
+    fn default() -> Record {
+        Record::new_type(String::new(), vec!())
+    }
+}
+
+impl Trace for Record {
+    fn trace(&self) -> IterGcThing {
+        let mut results : Vec<GcThing> = self.fields.iter()
+            .filter_map(|val| val.to_gc_thing())
+            .collect();
+        if let Some(record_type) = self.record_type {
+            results.push(GcThing::from_record_ptr(record_type));
+        }
+        results.into_iter()
+    }
+}
+
+impl hash::Hash for Record {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.type_name().hash(state);
+    }
+}
+
+/// A pointer to a `Record` on the heap.
+pub type RecordPtr = ArenaPtr<Record>;
+
+impl ToGcThing for RecordPtr {
+    fn to_gc_thing(&self) -> Option<GcThing> {
+        Some(GcThing::from_record_ptr(*self))
+    }
+}
+
+/// A rooted pointer to a `Record` on the heap.
+pub type RootedRecordPtr = Rooted<RecordPtr>;
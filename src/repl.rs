@@ -0,0 +1,454 @@
+// Copyright 2015 Nick Fitzgerald
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The interactive Read -> Evaluate -> Print loop, and reporting errors to the
+//! user.
+//!
+//! The REPL reads its input a line at a time into a buffer. Every complete
+//! datum in the buffer is evaluated and its value printed. When the buffer ends
+//! in the middle of a datum, the REPL waits for more input behind a `...>`
+//! continuation prompt, which is indented by the number of lists left open.
+//!
+//! Lines starting with a comma are meta-commands for the REPL itself, rather
+//! than Scheme code to evaluate. See `,help` for the list of commands.
+
+use std::env;
+use std::iter;
+use std::mem;
+use std::old_io;
+
+use clock;
+use diagnostic::{Diagnostic};
+use eval;
+use heap::{Heap};
+use line_editor::{self, Input, LineEditor};
+use pretty;
+use read::{is_plain_identifier, read_from_str, Location, Read};
+use value::{RootedValue};
+
+/// The name of the file in the user's home directory that REPL history is
+/// saved in.
+static HISTORY_FILE_NAME : &'static str = ".oxischeme_history";
+
+/// Return true if diagnostics written to stderr should be coloured, false
+/// otherwise.
+fn use_colour() -> bool {
+    old_io::stdio::stderr_raw().isatty()
+}
+
+/// Report the given diagnostic on stderr, quoting the source it refers to.
+pub fn report_diagnostic(diagnostic: &Diagnostic) {
+    let mut stderr = old_io::stdio::stderr();
+    (write!(&mut stderr, "{}", diagnostic.render(use_colour())))
+        .ok().expect("IO ERROR!");
+}
+
+/// Report the given error message on stderr.
+pub fn report_error(msg: &str) {
+    let mut stderr = old_io::stdio::stderr();
+    (writeln!(&mut stderr, "{}", msg)).ok().expect("IO ERROR!");
+}
+
+/// Report the most recent failure of the given reader on stderr.
+pub fn report_read_error<R: Reader>(reader: &Read<R>, msg: &str) {
+    match reader.diagnostics().last() {
+        Some(diagnostic) => report_diagnostic(diagnostic),
+        None             => report_error(msg),
+    }
+}
+
+/// Print the value of an evaluated form on stdout, breaking it over multiple
+/// lines if it is too wide for the terminal.
+fn print_result(val: &RootedValue) {
+    let width = line_editor::terminal_width().unwrap_or(pretty::DEFAULT_WIDTH);
+    println!("{}", pretty::pretty_print(&**val, width));
+}
+
+/// Analyze and evaluate the given form, reporting any error on stderr. Returns
+/// `None` without reporting anything when the form calls `exit`; check
+/// `Heap::exit_status` to tell the difference.
+pub fn evaluate_form(heap: &mut Heap,
+                     form: &RootedValue,
+                     location: Location) -> Option<RootedValue> {
+    let meaning = match eval::analyze(heap, form, location) {
+        Ok(meaning)     => meaning,
+        Err(diagnostic) => {
+            report_diagnostic(&diagnostic);
+            return None;
+        },
+    };
+
+    match eval::evaluate_meaning(heap, &meaning) {
+        Ok(val) => Some(val),
+        Err(e)  => {
+            if heap.exit_status().is_none() {
+                report_error(e.as_slice());
+            }
+            None
+        },
+    }
+}
+
+/// The help text printed by the `,help` command.
+static HELP : &'static str = "\
+,load FILE      Read and evaluate each form in FILE.
+,time EXPR      Evaluate EXPR and report the time taken and allocations made.
+,gc             Collect garbage and report heap statistics.
+,describe SYM   Describe the variable named SYM and its value.
+,meaning EXPR   Print the analyzed meaning of EXPR without evaluating it.
+,quit           Exit the REPL.
+,help           Print this help.";
+
+/// The state of the REPL between lines of input.
+pub struct Repl<'a> {
+    heap: &'a mut Heap,
+    /// Input that has not been evaluated yet, because it does not contain a
+    /// complete datum.
+    buffer: String,
+    /// The number of lists left open at the end of `buffer`.
+    open_lists: usize,
+    /// True once the user has asked to leave the REPL with `,quit` or by
+    /// calling `exit`.
+    quit: bool,
+}
+
+impl<'a> Repl<'a> {
+    /// Create a new `Repl` which evaluates forms in the given heap.
+    pub fn new(heap: &'a mut Heap) -> Repl<'a> {
+        Repl {
+            heap: heap,
+            buffer: String::new(),
+            open_lists: 0,
+            quit: false,
+        }
+    }
+
+    /// Run the REPL on stdin until EOF.
+    pub fn run(&mut self) {
+        println!("Welcome to oxischeme!");
+        println!("C-d to exit.");
+        println!("");
+
+        let history_path = env::home_dir().map(|home| home.join(HISTORY_FILE_NAME));
+        let mut editor = LineEditor::new(history_path);
+        while !self.quit {
+            let prompt = self.prompt();
+            let input = editor.read_line(prompt.as_slice(),
+                                         &|prefix| self.completions(prefix));
+            match input {
+                Input::Line(line)  => {
+                    editor.add_history(line.as_slice());
+                    self.eval_line(line.as_slice());
+                },
+                Input::Interrupted => {
+                    // Abandon any partially entered form.
+                    self.buffer.clear();
+                    self.open_lists = 0;
+                },
+                Input::Eof         => {
+                    println!("");
+                    return;
+                },
+            }
+        }
+    }
+
+    /// Get the completions of the given symbol prefix: the names of all
+    /// interned symbols and global variables that start with it.
+    pub fn completions(&self, prefix: &str) -> Vec<String> {
+        let mut completions : Vec<String> = self.heap.symbol_names().into_iter()
+            .chain(self.heap.environment.global_names().into_iter())
+            .filter(|name| name.starts_with(prefix) && is_plain_identifier(name.as_slice()))
+            .map(|name| name.clone())
+            .collect();
+        completions.sort();
+        completions.dedup();
+        completions
+    }
+
+    /// Get the prompt for the next line of input.
+    pub fn prompt(&self) -> String {
+        if self.buffer.is_empty() {
+            "oxischeme> ".to_string()
+        } else {
+            let indent : String = iter::repeat("  ").take(self.open_lists).collect();
+            format!("...> {}", indent)
+        }
+    }
+
+    /// Add the given line of input to the buffer, and evaluate each complete
+    /// datum in the buffer.
+    pub fn eval_line(&mut self, line: &str) {
+        if self.buffer.is_empty() && line.trim().starts_with(",") {
+            self.run_command(line.trim().slice_from(1));
+            if self.heap.exit_status().is_some() {
+                self.quit = true;
+            }
+            return;
+        }
+
+        self.buffer.push_str(line);
+        self.evaluate_buffer();
+    }
+
+    /// Run the given meta-command, which is everything on its line after the
+    /// leading comma.
+    fn run_command(&mut self, command: &str) {
+        let (name, arg) = match command.find(|c: char| c.is_whitespace()) {
+            Some(idx) => (command.slice_to(idx), command.slice_from(idx).trim()),
+            None      => (command, ""),
+        };
+
+        match name {
+            "load"     => self.load_command(arg),
+            "time"     => self.time_command(arg),
+            "gc"       => self.gc_command(),
+            "describe" => self.describe_command(arg),
+            "meaning"  => self.meaning_command(arg),
+            "quit"     => self.quit = true,
+            "help"     => println!("{}", HELP),
+            _          => report_error(format!("Unknown command: ,{} (try ,help)",
+                                               name).as_slice()),
+        }
+    }
+
+    /// Read the first datum of a meta-command's argument, reporting any error.
+    fn read_argument(&mut self, arg: &str) -> Option<(Location, RootedValue)> {
+        let mut reader = read_from_str(arg, self.heap, "stdin");
+        match reader.next() {
+            Some((location, Ok(form))) => Some((location, form)),
+            Some((_, Err(msg)))        => {
+                report_read_error(&reader, msg.as_slice());
+                None
+            },
+            None                       => {
+                report_error("Expected an expression");
+                None
+            },
+        }
+    }
+
+    /// `,load FILE`
+    fn load_command(&mut self, file_path: &str) {
+        if file_path.is_empty() {
+            report_error("Expected a file to load");
+            return;
+        }
+
+        if let Err(e) = eval::evaluate_file(self.heap, file_path) {
+            if self.heap.exit_status().is_none() {
+                report_error(e.as_slice());
+            }
+        }
+    }
+
+    /// `,time EXPR`
+    fn time_command(&mut self, arg: &str) {
+        let (location, form) = match self.read_argument(arg) {
+            Some(arg) => arg,
+            None      => return,
+        };
+
+        let allocations = self.heap.stats().total_allocations;
+        let start = clock::monotonic_ns();
+        let result = evaluate_form(self.heap, &form, location);
+        let elapsed = clock::monotonic_ns() - start;
+        let allocations = self.heap.stats().total_allocations - allocations;
+
+        if let Some(val) = result {
+            print_result(&val);
+        }
+        println!("; {}.{:03} ms, {} allocations",
+                 elapsed / 1_000_000,
+                 (elapsed / 1_000) % 1_000,
+                 allocations);
+    }
+
+    /// `,gc`
+    fn gc_command(&mut self) {
+        self.heap.collect_garbage();
+        println!("{}", self.heap.stats());
+    }
+
+    /// `,describe SYM`
+    fn describe_command(&mut self, name: &str) {
+        if name.is_empty() {
+            report_error("Expected a variable name to describe");
+            return;
+        }
+
+        let (i, j) = match self.heap.environment.lookup(&name.to_string()) {
+            Some(coords) => coords,
+            None         => {
+                println!("{} is not bound", name);
+                return;
+            },
+        };
+
+        let act = self.heap.global_activation();
+        match act.fetch(self.heap, i, j) {
+            Ok(val) => println!("{} is bound at ({} {}) to {}", name, i, j, *val),
+            Err(()) => println!("{} is bound at ({} {}) but not yet defined",
+                                name, i, j),
+        }
+    }
+
+    /// `,meaning EXPR`
+    fn meaning_command(&mut self, arg: &str) {
+        let (location, form) = match self.read_argument(arg) {
+            Some(arg) => arg,
+            None      => return,
+        };
+
+        match eval::analyze(self.heap, &form, location) {
+            Ok(meaning)     => println!("{}", meaning),
+            Err(diagnostic) => report_diagnostic(&diagnostic),
+        }
+    }
+
+    /// Evaluate each complete datum in the buffer, leaving only the start of an
+    /// incomplete datum, if any, in the buffer.
+    fn evaluate_buffer(&mut self) {
+        let buffer = mem::replace(&mut self.buffer, String::new());
+        let mut reader = read_from_str(buffer.as_slice(), self.heap, "stdin");
+        let mut consumed = 0;
+        self.open_lists = 0;
+
+        while let Some((location, read_result)) = reader.next() {
+            match read_result {
+                Ok(form) => {
+                    consumed = location.end_offset as usize;
+                    if let Some(val) = evaluate_form(self.heap, &form, location) {
+                        print_result(&val);
+                    }
+                    if self.heap.exit_status().is_some() {
+                        self.quit = true;
+                        return;
+                    }
+                    self.heap.collect_garbage();
+                },
+                Err(msg) => {
+                    if let Some(open_lists) = reader.incomplete() {
+                        // Wait for the rest of the datum.
+                        self.buffer = buffer.slice_from(consumed).to_string();
+                        self.open_lists = open_lists;
+                        return;
+                    }
+
+                    report_read_error(&reader, msg.as_slice());
+
+                    // Discard the rest of the line the error is on, but keep any
+                    // lines after it, which are unrelated to the error.
+                    let rest = buffer.slice_from(reader.current_location().offset as usize);
+                    if let Some(newline) = rest.find('\n') {
+                        self.buffer = rest.slice_from(newline + 1).to_string();
+                        self.evaluate_buffer();
+                    }
+                    return;
+                },
+            }
+        }
+    }
+}
+
+// TESTS -----------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heap::{Heap};
+
+    #[test]
+    fn test_repl_continuation_prompt() {
+        let heap = &mut Heap::new();
+        let mut repl = Repl::new(heap);
+        assert_eq!(repl.prompt(), "oxischeme> ");
+
+        repl.eval_line("(define f\n");
+        assert_eq!(repl.prompt(), "...>   ");
+
+        repl.eval_line("  (lambda (x)\n");
+        assert_eq!(repl.prompt(), "...>     ");
+
+        repl.eval_line("    (if (= x 0) 1 2)))\n");
+        assert_eq!(repl.prompt(), "oxischeme> ");
+    }
+
+    #[test]
+    fn test_repl_incomplete_string() {
+        let heap = &mut Heap::new();
+        let mut repl = Repl::new(heap);
+        repl.eval_line("1 \"hello\n");
+        assert_eq!(repl.prompt(), "...> ");
+        assert_eq!(repl.buffer, " \"hello\n");
+
+        repl.eval_line("world\"\n");
+        assert_eq!(repl.prompt(), "oxischeme> ");
+    }
+
+    #[test]
+    fn test_repl_error_keeps_later_lines() {
+        let heap = &mut Heap::new();
+        let mut repl = Repl::new(heap);
+        repl.eval_line("(1 #\\bogus) (2\n(3\n");
+        assert_eq!(repl.buffer, "(3\n");
+        assert_eq!(repl.open_lists, 1);
+    }
+
+    #[test]
+    fn test_repl_completions() {
+        let heap = &mut Heap::new();
+        let mut repl = Repl::new(heap);
+        repl.eval_line("(define char-thing 1)\n");
+        assert_eq!(repl.completions("char-u"),
+                   vec!("char-upcase".to_string(),
+                        "char-upper-case?".to_string()));
+        assert!(repl.completions("char-t").contains(&"char-thing".to_string()));
+    }
+
+    #[test]
+    fn test_repl_commands() {
+        let heap = &mut Heap::new();
+        let mut repl = Repl::new(heap);
+        repl.eval_line(",time (define x (cons 1 2))\n");
+        repl.eval_line(",describe x\n");
+        repl.eval_line(",meaning (lambda (y) (cons x y))\n");
+        repl.eval_line(",gc\n");
+        repl.eval_line(",bogus\n");
+        assert_eq!(repl.prompt(), "oxischeme> ");
+        assert!(!repl.quit);
+
+        repl.eval_line(",quit\n");
+        assert!(repl.quit);
+    }
+
+    #[test]
+    fn test_repl_command_only_at_start_of_datum() {
+        let heap = &mut Heap::new();
+        let mut repl = Repl::new(heap);
+        repl.eval_line("(list 1\n");
+        repl.eval_line(",quit\n");
+        assert!(!repl.quit);
+    }
+
+    #[test]
+    fn test_repl_exit() {
+        let heap = &mut Heap::new();
+        let mut repl = Repl::new(heap);
+        repl.eval_line("(exit 2) (define after-exit 1)\n");
+        assert!(repl.quit);
+        assert_eq!(repl.heap.exit_status(), Some(2));
+        assert!(repl.heap.environment.lookup(&"after-exit".to_string()).is_none());
+    }
+}
@@ -14,16 +14,21 @@
 
 //! Scheme value implementation.
 
-use std::collections::{HashSet};
+use std::collections::{HashMap, HashSet};
 use std::default::{Default};
 use std::fmt;
 use std::hash;
+use std::mem;
 
 use environment::{ActivationPtr, RootedActivationPtr};
 use eval::{Meaning, TrampolineResult};
+use hash_table::{HashTable, HashTablePtr, RootedHashTablePtr};
 use heap::{ArenaPtr, GcThing, Heap, IterGcThing, Rooted, RootedStringPtr,
            StringPtr, ToGcThing, Trace};
+use port::{Port, PortPtr, RootedPortPtr};
 use primitives::{PrimitiveFunction};
+use read::{character_name, is_plain_identifier};
+use record::{Record, RecordPtr, RootedRecordPtr};
 
 /// A cons cell is a pair of `car` and `cdr` values. A list is one or more cons
 /// cells, daisy chained together via the `cdr`. A list is "proper" if the last
@@ -173,6 +178,11 @@ impl hash::Hash for Primitive {
 }
 
 impl Primitive {
+    /// Get the name of this primitive.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
     #[inline]
     pub fn call(&self, heap: &mut Heap, args: Vec<RootedValue>) -> TrampolineResult {
         (self.function)(heap, args)
@@ -185,6 +195,57 @@ impl fmt::Debug for Primitive {
     }
 }
 
+/// An inexact real number.
+///
+/// Like the rest of `Value`, its `Eq` and `Hash` implementations are identity:
+/// two floats are identical when they have the same bit pattern. That makes a
+/// `NaN` identical to itself, and `0.0` different from `-0.0`. Use the
+/// underlying `f64` for numeric comparison.
+#[derive(Copy, Debug)]
+pub struct Float(pub f64);
+
+impl Float {
+    fn bits(&self) -> u64 {
+        unsafe { mem::transmute(self.0) }
+    }
+}
+
+impl PartialEq for Float {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.bits() == rhs.bits()
+    }
+}
+
+impl Eq for Float { }
+
+impl hash::Hash for Float {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.bits().hash(state);
+    }
+}
+
+impl fmt::Display for Float {
+    /// Print the float so that it reads back as the same inexact number: with a
+    /// decimal point even when it is integral, and with R7RS's spelling of the
+    /// infinities and NaN.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let x = self.0;
+        if x.is_nan() {
+            return write!(f, "+nan.0");
+        }
+        if x.is_infinite() {
+            return write!(f, "{}inf.0", if x > 0.0 { "+" } else { "-" });
+        }
+
+        let str = format!("{}", x);
+        if str.contains(".") {
+            write!(f, "{}", str)
+        } else {
+            write!(f, "{}.0", str)
+        }
+    }
+}
+
 /// `Value` represents a scheme value of any type.
 ///
 /// Note that `Eq` and `PartialEq` are object identity, not structural
@@ -207,6 +268,9 @@ pub enum Value {
     /// Scheme integers are represented as 64 bit integers.
     Integer(i64),
 
+    /// Inexact real numbers are represented as 64 bit floats.
+    Float(Float),
+
     /// Scheme booleans are represented with `bool`.
     Boolean(bool),
 
@@ -220,6 +284,20 @@ pub enum Value {
     /// A primitive Scheme procedure is just a pointer to a `Primitive` type
     /// function pointer.
     Primitive(Primitive),
+
+    /// Scheme ports are a pointer to a GC-managed `Port`.
+    Port(PortPtr),
+
+    /// Hash tables are a pointer to a GC-managed `HashTable`.
+    HashTable(HashTablePtr),
+
+    /// Record types and their instances are a pointer to a GC-managed
+    /// `Record`.
+    Record(RecordPtr),
+
+    /// The end of file object, returned by input procedures when there is no
+    /// more input. It is distinct from every value that can be read.
+    EofObject,
 }
 
 /// # `Value` Constructors
@@ -229,6 +307,11 @@ impl Value {
         Value::Integer(i)
     }
 
+    /// Create a new inexact real number value.
+    pub fn new_float(x: f64) -> Value {
+        Value::Float(Float(x))
+    }
+
     /// Create a new boolean value.
     pub fn new_boolean(b: bool) -> Value {
         Value::Boolean(b)
@@ -277,6 +360,24 @@ impl Value {
         Rooted::new(heap, Value::String(*value))
     }
 
+    /// Allocate a new port value with the given source or sink.
+    pub fn new_port(heap: &mut Heap, port: Port) -> RootedValue {
+        let port = heap.allocate_port(port);
+        Rooted::new(heap, Value::Port(*port))
+    }
+
+    /// Allocate a new hash table value.
+    pub fn new_hash_table(heap: &mut Heap, table: HashTable) -> RootedValue {
+        let table = heap.allocate_hash_table(table);
+        Rooted::new(heap, Value::HashTable(*table))
+    }
+
+    /// Allocate a new record type or record instance value.
+    pub fn new_record(heap: &mut Heap, record: Record) -> RootedValue {
+        let record = heap.allocate_record(record);
+        Rooted::new(heap, Value::Record(*record))
+    }
+
     /// Create a new symbol value with the given string.
     pub fn new_symbol(heap: &mut Heap, str: RootedStringPtr) -> RootedValue {
         Rooted::new(heap, Value::Symbol(*str))
@@ -316,6 +417,18 @@ impl Value {
         !self.is_pair()
     }
 
+    /// Get a `Display`able version of this value which prints datum labels for
+    /// all shared structure, e.g. `(#0=(1) #0#)`, rather than only for cycles.
+    pub fn shared(&self) -> PrintedValue {
+        self.printed(PrintStyle::WriteShared)
+    }
+
+    /// Get a `Display`able version of this value which prints it in the given
+    /// style.
+    pub fn printed(&self, style: PrintStyle) -> PrintedValue {
+        PrintedValue { val: *self, style: style }
+    }
+
     /// Coerce this symbol value to a `StringPtr` to the symbol's string name.
     pub fn to_symbol(&self, heap: &mut Heap) -> Option<RootedStringPtr> {
         match *self {
@@ -324,6 +437,40 @@ impl Value {
         }
     }
 
+    /// Coerce this port value to a `PortPtr` to the `Port` it is referring to.
+    pub fn to_port(&self, heap: &mut Heap) -> Option<RootedPortPtr> {
+        match *self {
+            Value::Port(p) => Some(Rooted::new(heap, p)),
+            _              => None,
+        }
+    }
+
+    /// Coerce this hash table value to a `HashTablePtr` to the `HashTable` it
+    /// is referring to.
+    pub fn to_hash_table(&self, heap: &mut Heap) -> Option<RootedHashTablePtr> {
+        match *self {
+            Value::HashTable(t) => Some(Rooted::new(heap, t)),
+            _                   => None,
+        }
+    }
+
+    /// Coerce this record value to a `RecordPtr` to the `Record` it is
+    /// referring to.
+    pub fn to_record(&self, heap: &mut Heap) -> Option<RootedRecordPtr> {
+        match *self {
+            Value::Record(r) => Some(Rooted::new(heap, r)),
+            _                => None,
+        }
+    }
+
+    /// Coerce this string value to a `StringPtr` to its underlying string.
+    pub fn to_string_ptr(&self, heap: &mut Heap) -> Option<RootedStringPtr> {
+        match *self {
+            Value::String(str) => Some(Rooted::new(heap, str)),
+            _                  => None,
+        }
+    }
+
     /// Coerce this pair value to a `ConsPtr` to the cons cell this pair is
     /// referring to.
     pub fn to_pair(&self, heap: &mut Heap) -> Option<RootedConsPtr> {
@@ -350,6 +497,22 @@ impl Value {
         }
     }
 
+    /// Coerce this inexact real number value to its underlying `f64`.
+    pub fn to_float(&self) -> Option<f64> {
+        match *self {
+            Value::Float(ref x) => Some(x.0),
+            _                   => None,
+        }
+    }
+
+    /// Coerce this character value to its underlying `char`.
+    pub fn to_character(&self) -> Option<char> {
+        match *self {
+            Value::Character(ref c) => Some(*c),
+            _                       => None,
+        }
+    }
+
     /// Assuming that this value is a proper list, get the length of the list.
     pub fn len(&self) -> Result<u64, ()> {
         match *self {
@@ -370,6 +533,54 @@ impl Value {
     }
 }
 
+/// # `Value` Equivalence
+impl Value {
+    /// Scheme's `eqv?`. Numbers and characters are compared by value, and
+    /// everything else by identity. Since numbers and characters are not
+    /// allocated on the heap, this is the same as `eq?`. Floats are compared by
+    /// their bits, so `+nan.0` is `eqv?` to itself and `0.0` is not `eqv?` to
+    /// `-0.0`, as R7RS requires.
+    pub fn eqv(&self, other: &Value) -> bool {
+        *self == *other
+    }
+
+    /// Scheme's `equal?`. Pairs are compared by their contents, recursively,
+    /// and so are strings. Everything else is compared with `eqv?`.
+    ///
+    /// Comparison terminates on cyclic structure: a pair of pairs that is
+    /// already being compared is assumed to be equal when it is reached again,
+    /// so two cycles are equal if unrolling them would never find a
+    /// difference.
+    pub fn equal(&self, other: &Value) -> bool {
+        let mut assumed = HashSet::new();
+        let mut pending = vec!((*self, *other));
+
+        while let Some((a, b)) = pending.pop() {
+            match (a, b) {
+                (Value::Pair(x), Value::Pair(y))     => {
+                    if x == y || !assumed.insert((x, y)) {
+                        continue;
+                    }
+                    pending.push((x.cdr, y.cdr));
+                    pending.push((x.car, y.car));
+                },
+                (Value::String(x), Value::String(y)) => {
+                    if *x != *y {
+                        return false;
+                    }
+                },
+                (a, b)                               => {
+                    if !a.eqv(&b) {
+                        return false;
+                    }
+                },
+            }
+        }
+
+        true
+    }
+}
+
 impl ToGcThing for Value {
     fn to_gc_thing(&self) -> Option<GcThing> {
         match *self {
@@ -377,77 +588,260 @@ impl ToGcThing for Value {
             Value::Symbol(sym)  => Some(GcThing::from_string_ptr(sym)),
             Value::Pair(cons)   => Some(GcThing::from_cons_ptr(cons)),
             Value::Procedure(p) => Some(GcThing::from_procedure_ptr(p)),
+            Value::Port(p)      => Some(GcThing::from_port_ptr(p)),
+            Value::HashTable(t) => Some(GcThing::from_hash_table_ptr(t)),
+            Value::Record(r)    => Some(GcThing::from_record_ptr(r)),
             _                   => None,
         }
     }
 }
 
-fn print(f: &mut fmt::Formatter, val: &Value, seen: &mut HashSet<ConsPtr>) -> fmt::Result {
-    match *val {
-        Value::EmptyList        => write!(f, "()"),
-        Value::Pair(ref cons)   => {
-            try!(write!(f, "("));
-            try!(print_pair(f, cons, seen));
-            write!(f, ")")
-        },
-        Value::String(ref str)  => {
-            try!(write!(f, "\""));
-            try!(write!(f, "{}", **str));
-            write!(f, "\"")
-        },
-        Value::Symbol(ref s)    => write!(f, "{}", **s),
-        Value::Integer(ref i)   => write!(f, "{}", i),
-        Value::Boolean(ref b)   => {
-            write!(f, "{}", if *b {
-                "#t"
-            } else {
-                "#f"
-            })
-        },
-        Value::Character(ref c) => match *c {
-            '\n' => write!(f, "#\\newline"),
-            '\t' => write!(f, "#\\tab"),
-            ' '  => write!(f, "#\\space"),
-            _    => write!(f, "#\\{}", c),
-        },
-        Value::Procedure(ref p) => write!(f, "#<procedure {:?}>", p),
-        Value::Primitive(ref p) => write!(f, "#<procedure {:?}>", p),
-    }
+/// The ways of printing a value, corresponding to Scheme's `write`,
+/// `write-shared`, `write-simple`, and `display` procedures.
+#[derive(Copy, Debug, PartialEq, Eq)]
+pub enum PrintStyle {
+    /// Print the value so that it can be read back in, with datum labels for
+    /// cycles.
+    Write,
+    /// Like `Write`, but with datum labels for all shared structure.
+    WriteShared,
+    /// Like `Write`, but without any datum labels. Does not terminate on cyclic
+    /// structures!
+    WriteSimple,
+    /// Print strings, symbols and characters as their contents, rather than as
+    /// literals, and otherwise like `Write`.
+    Display,
+}
+
+/// The state for printing a value: which pairs need datum labels, e.g.
+/// `#0=(a . #0#)`, and the labels assigned to them so far.
+struct Printer {
+    /// Pairs that need a datum label, mapped to their label once it has been
+    /// printed.
+    labels: HashMap<ConsPtr, Option<u64>>,
+    next_label: u64,
+    style: PrintStyle,
+    /// The records currently being printed, so that a record that contains
+    /// itself is abbreviated rather than printed forever.
+    records: HashSet<RecordPtr>,
 }
 
-/// Print the given cons pair, without the containing "(" and ")".
-fn print_pair(f: &mut fmt::Formatter, cons: &ConsPtr, seen: &mut HashSet<ConsPtr>) -> fmt::Result {
-    if seen.contains(cons) {
-        return write!(f, "<cyclic value>");
+impl Printer {
+    /// Create a new `Printer` for the given value and style, finding the pairs
+    /// that need datum labels.
+    fn new(val: &Value, style: PrintStyle) -> Printer {
+        let labeled = match style {
+            PrintStyle::WriteSimple => HashSet::new(),
+            PrintStyle::WriteShared => find_datum_labels(val, true),
+            _                       => find_datum_labels(val, false),
+        };
+        Printer {
+            labels: labeled.into_iter().map(|cons| (cons, None)).collect(),
+            next_label: 0,
+            style: style,
+            records: HashSet::new(),
+        }
+    }
+
+    /// Print the given value.
+    fn print(&mut self, f: &mut fmt::Formatter, val: &Value) -> fmt::Result {
+        match *val {
+            Value::EmptyList        => write!(f, "()"),
+            Value::Pair(ref cons)   => {
+                let label = self.labels.get(cons).map(|l| *l);
+                match label {
+                    Some(Some(n)) => return write!(f, "#{}#", n),
+                    Some(None)    => {
+                        let n = self.next_label;
+                        self.next_label += 1;
+                        try!(write!(f, "#{}=", n));
+                        self.labels.insert(*cons, Some(n));
+                    },
+                    None          => { },
+                };
+                try!(write!(f, "("));
+                try!(self.print_pair(f, cons));
+                write!(f, ")")
+            },
+            Value::String(ref str) if self.style == PrintStyle::Display => {
+                write!(f, "{}", str.as_slice())
+            },
+            Value::Symbol(ref s) if self.style == PrintStyle::Display => {
+                write!(f, "{}", s.as_slice())
+            },
+            Value::Character(c) if self.style == PrintStyle::Display => {
+                write!(f, "{}", c)
+            },
+            Value::String(ref str)  => print_string(f, str.as_slice()),
+            Value::Symbol(ref s)    => print_symbol(f, s.as_slice()),
+            Value::Integer(ref i)   => write!(f, "{}", i),
+            Value::Float(ref x)     => write!(f, "{}", x),
+            Value::Boolean(ref b)   => {
+                write!(f, "{}", if *b {
+                    "#t"
+                } else {
+                    "#f"
+                })
+            },
+            Value::Character(ref c) => match character_name(*c) {
+                Some(name)             => write!(f, "#\\{}", name),
+                None if c.is_control() => write!(f, "#\\x{:x}", *c as u32),
+                None                   => write!(f, "#\\{}", c),
+            },
+            Value::Procedure(ref p) => write!(f, "#<procedure {:?}>", p),
+            Value::Primitive(ref p) => write!(f, "#<procedure {:?}>", p),
+            Value::Port(ref p)      => write!(f, "{}", **p),
+            Value::HashTable(ref t) => write!(f, "{}", **t),
+            Value::Record(ref r)    => self.print_record(f, r),
+            Value::EofObject        => write!(f, "#<eof>"),
+        }
     }
-    seen.insert(*cons);
 
-    try!(print(f, &cons.car, seen));
+    /// Print the given record type or record instance.
+    fn print_record(&mut self, f: &mut fmt::Formatter, record: &RecordPtr) -> fmt::Result {
+        if record.is_type() {
+            return write!(f, "#<record-type {}>", record.type_name());
+        }
+        if self.records.contains(record) {
+            return write!(f, "#<record {} ...>", record.type_name());
+        }
 
-    if let Value::Pair(rest) = cons.cdr {
-        if seen.contains(&rest) {
-            return write!(f, " . <cyclic value>");
+        self.records.insert(*record);
+        try!(write!(f, "#<record {}", record.type_name()));
+        for (name, val) in record.field_names().iter().zip(record.fields().iter()) {
+            try!(write!(f, " {}: ", name));
+            try!(self.print(f, val));
+        }
+        self.records.remove(record);
+        write!(f, ">")
+    }
+
+    /// Print the given cons pair, without the containing "(" and ")".
+    fn print_pair(&mut self, f: &mut fmt::Formatter, cons: &ConsPtr) -> fmt::Result {
+        try!(self.print(f, &cons.car));
+
+        let mut rest = cons.cdr;
+        loop {
+            match rest {
+                Value::EmptyList                                    => return Ok(()),
+                Value::Pair(cdr) if !self.labels.contains_key(&cdr) => {
+                    try!(write!(f, " "));
+                    try!(self.print(f, &cdr.car));
+                    rest = cdr.cdr;
+                },
+                ref val                                             => {
+                    try!(write!(f, " . "));
+                    return self.print(f, val);
+                },
+            }
         }
     }
+}
 
-    match cons.cdr {
-        Value::EmptyList => Ok(()),
-        Value::Pair(ref cdr) => {
-            try!(write!(f, " "));
-            print_pair(f, cdr, seen)
-        },
-        ref val => {
-            try!(write!(f, " . "));
-            print(f, val, seen)
-        },
+/// Find the pairs within the given value that need datum labels. If `shared` is
+/// true, every pair that is referenced more than once gets a datum label;
+/// otherwise only the pairs needed to break cycles do.
+pub fn find_datum_labels(val: &Value, shared: bool) -> HashSet<ConsPtr> {
+    enum Visit {
+        Enter(Value),
+        Exit(ConsPtr),
+    }
+
+    let mut labels = HashSet::new();
+    let mut on_path : HashSet<ConsPtr> = HashSet::new();
+    let mut done : HashSet<ConsPtr> = HashSet::new();
+    let mut records : HashSet<RecordPtr> = HashSet::new();
+    let mut pending = vec!(Visit::Enter(*val));
+
+    while let Some(visit) = pending.pop() {
+        match visit {
+            Visit::Enter(Value::Pair(cons))     => {
+                if on_path.contains(&cons) || (shared && done.contains(&cons)) {
+                    labels.insert(cons);
+                } else if !done.contains(&cons) {
+                    on_path.insert(cons);
+                    pending.push(Visit::Exit(cons));
+                    pending.push(Visit::Enter(cons.cdr));
+                    pending.push(Visit::Enter(cons.car));
+                }
+            },
+            Visit::Enter(Value::Record(record)) => {
+                // Records don't get datum labels themselves, but the pairs in
+                // their fields may need them.
+                if records.insert(record) {
+                    for val in record.fields().iter().rev() {
+                        pending.push(Visit::Enter(*val));
+                    }
+                }
+            },
+            Visit::Enter(_)                     => { },
+            Visit::Exit(cons)                   => {
+                on_path.remove(&cons);
+                done.insert(cons);
+            },
+        }
+    }
+
+    labels
+}
+
+/// Print the given symbol name, surrounded by vertical bars and with any
+/// special characters escaped if it would not otherwise read back in as the
+/// same symbol, e.g. `|hello world|`.
+fn print_symbol(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+    if is_plain_identifier(name) {
+        return write!(f, "{}", name);
     }
+
+    try!(write!(f, "|"));
+    for c in name.chars() {
+        try!(match c {
+            '|'                  => write!(f, "\\|"),
+            '\\'                 => write!(f, "\\\\"),
+            c if c.is_control()  => write!(f, "\\x{:x};", c as u32),
+            c                    => write!(f, "{}", c),
+        });
+    }
+    write!(f, "|")
+}
+
+/// Print the given string as a string literal, surrounded by double quotes and
+/// with any characters that would not read back in correctly escaped.
+fn print_string(f: &mut fmt::Formatter, str: &str) -> fmt::Result {
+    try!(write!(f, "\""));
+    for c in str.chars() {
+        try!(match c {
+            '"'                  => write!(f, "\\\""),
+            '\\'                 => write!(f, "\\\\"),
+            '\x07'               => write!(f, "\\a"),
+            '\x08'               => write!(f, "\\b"),
+            '\t'                 => write!(f, "\\t"),
+            '\n'                 => write!(f, "\\n"),
+            '\r'                 => write!(f, "\\r"),
+            c if c.is_control()  => write!(f, "\\x{:x};", c as u32),
+            c                    => write!(f, "{}", c),
+        });
+    }
+    write!(f, "\"")
 }
 
 impl fmt::Display for Value {
     /// Print the given value's text representation to the given writer. This is
-    /// the opposite of `Read`.
+    /// the opposite of `Read`. Cyclic structures are printed with datum labels.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Printer::new(self, PrintStyle::Write).print(f, self)
+    }
+}
+
+/// A value that is printed in a particular `PrintStyle`. See `Value::printed`.
+pub struct PrintedValue {
+    val: Value,
+    style: PrintStyle,
+}
+
+impl fmt::Display for PrintedValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        print(f, self, &mut HashSet::new())
+        Printer::new(&self.val, self.style).print(f, &self.val)
     }
 }
 
@@ -534,6 +928,8 @@ impl Cons {
 mod tests {
     use eval::{evaluate_file};
     use heap::{Heap};
+    use read::{read_from_str};
+    use value::{PrintStyle};
 
     #[test]
     fn test_print_cycle() {
@@ -543,4 +939,54 @@ mod tests {
             .expect("Should be able to eval a file.");
         assert!(true, "Shouldn't get stuck in an infinite loop printing a cyclic value");
     }
+
+    #[test]
+    fn test_print_datum_labels() {
+        let input = "#0=(1 . #0#) #0=(a #0#) (#0=(1) #0#) (#0=(1) #0# . #0#)";
+        let heap = &mut Heap::new();
+        let results : Vec<String> = read_from_str(input, heap, "test_print_datum_labels")
+            .map(|(_, r)| {
+                let val = r.ok().expect("Should not get a read error");
+                format!("{} {}", *val, val.shared())
+            })
+            .collect();
+        assert_eq!(results, vec!("#0=(1 . #0#) #0=(1 . #0#)".to_string(),
+                                 "#0=(a #0#) #0=(a #0#)".to_string(),
+                                 "((1) (1)) (#0=(1) #0#)".to_string(),
+                                 "((1) (1) 1) (#0=(1) #0# . #0#)".to_string()));
+    }
+
+    #[test]
+    fn test_equal() {
+        let heap = &mut Heap::new();
+        let mut reader = read_from_str("(1 \"two\" (#\\3)) (1 \"two\" (#\\3)) (1 \"two\" (3)) \
+                                        #0=(a b . #0#) #1=(a b a b . #1#) #2=(a b c . #2#)",
+                                       heap, "test_equal");
+        let mut vals = vec!();
+        while let Some((_, result)) = reader.next() {
+            vals.push(result.ok().expect("Should not get a read error"));
+        }
+        assert!(vals[0].equal(&*vals[1]));
+        assert!(!vals[0].eqv(&*vals[1]));
+        assert!(!vals[0].equal(&*vals[2]));
+        assert!(vals[3].equal(&*vals[4]));
+        assert!(!vals[3].equal(&*vals[5]));
+    }
+
+    #[test]
+    fn test_print_styles() {
+        let input = "(\"a\\n\" #\\b |c d| #0=(1) #0#)";
+        let heap = &mut Heap::new();
+        let (_, result) = read_from_str(input, heap, "test_print_styles").next()
+            .expect("Should read a datum");
+        let val = result.ok().expect("Should not get a read error");
+        assert_eq!(format!("{}", val.printed(PrintStyle::Write)),
+                   "(\"a\\n\" #\\b |c d| (1) (1))");
+        assert_eq!(format!("{}", val.printed(PrintStyle::WriteSimple)),
+                   "(\"a\\n\" #\\b |c d| (1) (1))");
+        assert_eq!(format!("{}", val.printed(PrintStyle::WriteShared)),
+                   "(\"a\\n\" #\\b |c d| #0=(1) #0#)");
+        assert_eq!(format!("{}", val.printed(PrintStyle::Display)),
+                   "(a\n b c d (1) (1))");
+    }
 }
@@ -0,0 +1,2 @@
+(define f
+  (lambda (x 1) x))
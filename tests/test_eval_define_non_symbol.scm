@@ -0,0 +1 @@
+(define 5 6)
@@ -0,0 +1,26 @@
+(define-record-type <point> (make-point x y) point?
+  (x point-x set-point-x!)
+  (y point-y))
+
+(define p (make-point 1 2))
+(set-point-x! p 3)
+
+(define-record-type <node> (make-node value) node?
+  (value node-value)
+  (next node-next set-node-next!))
+
+(define local-box
+  (lambda (n)
+    (define-record-type <box> (make-box value) box?
+      (value unbox))
+    (unbox (make-box n))))
+
+(list (point? p)
+      (point? 5)
+      (node? p)
+      (point-x p)
+      (point-y p)
+      p
+      (make-node 1)
+      (local-box 7)
+      <point>)
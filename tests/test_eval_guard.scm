@@ -0,0 +1,43 @@
+(define catch
+  (lambda (thunk)
+    (guard (e (#t e))
+      (thunk))))
+
+(define open-error
+  (catch (lambda () (open-input-file "./tests/no-such-file"))))
+(define delete-error
+  (catch (lambda () (delete-file "./tests/no-such-file"))))
+(define read-error
+  (catch (lambda () (read (open-input-string ")")))))
+(define user-error
+  (catch (lambda () (error "oops" 1 2))))
+(define car-error
+  (catch (lambda () (car 5))))
+
+(list (file-error? open-error)
+      (error-object-irritants open-error)
+      (file-error? delete-error)
+      (read-error? read-error)
+      (file-error? read-error)
+      (error-object-message user-error)
+      (error-object-irritants user-error)
+      (error-object? car-error)
+      (error-object-message car-error)
+      (guard (e ((string? e) 'string)
+                ((symbol? e) (list 'symbol e)))
+        (raise 'boom))
+      (guard (e ((assq 'a e) => cdr)
+                ((assq 'b e)))
+        (raise (list (cons 'a 42))))
+      (guard (e ((assq 'a e) => cdr)
+                ((assq 'b e)))
+        (raise (list (cons 'b 23))))
+      (guard (e ((string? e) 'string)
+                (else 'else))
+        (raise 'boom))
+      (with-exception-handler
+        (lambda (e) 10)
+        (lambda ()
+          (+ 1 (guard (e ((string? e) 'string))
+                 (raise-continuable 'not-a-string)))))
+      (catch (lambda () 'no-error)))
@@ -0,0 +1,5 @@
+(list (char<? #\a #\b #\c)
+      (char<? #\a #\c #\b)
+      (char>=? #\b #\b #\a)
+      (char-ci=? #\a #\A)
+      (char=? #\a #\A))
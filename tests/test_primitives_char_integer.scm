@@ -0,0 +1,3 @@
+(list (char->integer #\A)
+      (char->integer (integer->char 955))
+      (char=? (integer->char 97) #\a))
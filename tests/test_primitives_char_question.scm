@@ -0,0 +1 @@
+(cons (char? #\a) (char? "a"))
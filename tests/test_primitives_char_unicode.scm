@@ -0,0 +1,8 @@
+(list (char-alphabetic? (integer->char 955))
+      (char-numeric? (integer->char 1633))
+      (digit-value (integer->char 1633))
+      (digit-value #\a)
+      (char-whitespace? (integer->char 12288))
+      (char->integer (char-upcase (integer->char 955)))
+      (char-downcase #\A)
+      (char-foldcase #\Z))
@@ -0,0 +1 @@
+(list (inexact? (current-second)) (< 1.0e9 (current-second)))
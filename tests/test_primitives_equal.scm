@@ -0,0 +1,15 @@
+(define cycle (list 1 2))
+(set-cdr! (cdr cycle) cycle)
+(define other-cycle (list 1 2 1 2))
+(set-cdr! (cdr (cdr (cdr other-cycle))) other-cycle)
+
+(list (eqv? 100 100)
+      (eqv? #\a #\a)
+      (eqv? "abc" "abc")
+      (equal? (list 1 "abc" '(#\d)) (list 1 "abc" '(#\d)))
+      (equal? '(1 2) '(1 2 3))
+      (equal? cycle other-cycle)
+      (equal? "abc" "abc")
+      (assoc "b" (list (list "a" 1) (list "b" 2)))
+      (member '(b) '((a) (b) c))
+      (delete-duplicates (list "a" "b" "a")))
@@ -0,0 +1,29 @@
+(define seen '())
+(define note
+  (lambda (e)
+    (set! seen (cons e seen))))
+
+(define returned-from-raise
+  (guard (e ((error-object? e) (error-object-message e)))
+    (with-exception-handler
+      (lambda (e) (note e) 'ignored)
+      (lambda () (+ 1 (raise 'x))))))
+
+(define saw-file-error
+  (guard (e (#t (file-error? (car seen))))
+    (with-exception-handler
+      note
+      (lambda () (open-input-file "./tests/no-such-file")))))
+
+(list returned-from-raise
+      saw-file-error
+      (car (cdr seen))
+      (with-exception-handler
+        (lambda (e) (* e 2))
+        (lambda () (+ 1 (raise-continuable 20))))
+      (with-exception-handler
+        (lambda (e) (+ e 1))
+        (lambda ()
+          (with-exception-handler
+            (lambda (e) (raise-continuable (* e 10)))
+            (lambda () (raise-continuable 4))))))
@@ -0,0 +1,3 @@
+(define f (lambda () (exit 3) 'not-reached))
+(f)
+'also-not-reached
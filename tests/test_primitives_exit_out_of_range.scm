@@ -0,0 +1 @@
+(exit 4294967297)
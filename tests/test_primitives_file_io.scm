@@ -0,0 +1,26 @@
+(define path "./tests/test_primitives_file_io.tmp")
+
+(call-with-output-file path
+  (lambda (port)
+    (write-string "hello" port)
+    (newline port)
+    (write-char #\! port)))
+
+(define first-line (call-with-input-file path read-line))
+
+(with-output-to-file path
+  (lambda ()
+    (write-string "bye")))
+
+(define port (open-input-file path))
+(define second-line (read-line port))
+(define at-end (eof-object? (read-char port)))
+(close-port port)
+
+(define binary-port (open-binary-input-file path))
+(define byte (read-u8 binary-port))
+(close-port binary-port)
+
+(delete-file path)
+
+(list first-line second-line at-end byte (file-exists? path))
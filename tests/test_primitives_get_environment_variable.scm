@@ -0,0 +1,2 @@
+(cons (string? (get-environment-variable "PATH"))
+      (get-environment-variable "OXISCHEME_SURELY_NOT_SET"))
@@ -0,0 +1,40 @@
+(define table (make-hash-table))
+(hash-table-set! table (list 1 2) 1)
+(hash-table-set! table 1 'one)
+(hash-table-set! table "two" 2)
+(hash-table-delete! table 1)
+
+(define eq-table (make-eq-hash-table))
+(hash-table-set! eq-table "key" 1)
+
+(define strings (make-string-hash-table))
+(hash-table-set! strings "a" 1)
+(hash-table-set! strings "a" 2)
+
+(define counts (make-hash-table eqv?))
+(hash-table-update!/default counts 'x (lambda (n) (+ n 1)) 10)
+(hash-table-update! counts 'y (lambda (n) (+ n 1)) (lambda () 0))
+
+(define sum 0)
+(hash-table-walk counts (lambda (key val) (set! sum (+ sum val))))
+
+(define single (make-equal-hash-table))
+(hash-table-set! single 'a 1)
+
+(list (hash-table? table)
+      (hash-table-count table)
+      (hash-table-ref table (list 1 2))
+      (hash-table-ref/default table 1 'gone)
+      (hash-table-ref table "two")
+      (hash-table-ref/default eq-table "key" #f)
+      (hash-table-keys single)
+      strings
+      (hash-table-ref strings "a")
+      (hash-table-ref table 'missing (lambda () 'default))
+      (hash-table-ref counts 'x)
+      (hash-table-ref counts 'y)
+      (hash-table-contains? counts 'z)
+      sum
+      (hash-table-values single)
+      (hash-table->alist single)
+      (hash-table? '()))
@@ -0,0 +1,13 @@
+(list (+ 1 2.5)
+      (* 1.5 2)
+      (- 0.5 1)
+      (/ 1 4.0)
+      (/ 7 2)
+      (/ 1.0 0)
+      (< 1 1.5)
+      (= 2 2.0)
+      (number? 1.5)
+      (exact? 1.5)
+      (inexact? 1.5)
+      (exact 4.0)
+      (inexact 3))
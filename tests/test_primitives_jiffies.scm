@@ -0,0 +1,2 @@
+(define start (current-jiffy))
+(not (< (current-jiffy) start))
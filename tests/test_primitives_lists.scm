@@ -0,0 +1,20 @@
+(define sum 0)
+(for-each (lambda (a b) (set! sum (+ sum (* a b)))) '(1 2 3) '(4 5 6 7))
+(define tail (list 5 6))
+(define alist '((a 1) (b 2) (c 3)))
+(list (map (lambda (x) (* x x)) '(1 2 3))
+      (map + '(1 2 3) '(10 20))
+      sum
+      (append '(1) '(2 3) '() '(4 . 5))
+      (append)
+      (append! (list 1 2) '() (list 3) tail)
+      (reverse '(1 2 3))
+      (list-tail '(1 2 3 4) 2)
+      (list-ref '(a b c) 1)
+      (list-copy '(1 2 . 3))
+      (last-pair '(1 2 3))
+      (memq 'c '(a b c d))
+      (member 2 '(1 2 3) (lambda (a b) (< a b)))
+      (assq 'b alist)
+      (assoc 'd alist)
+      (eq? (list-tail (append! (list 1) tail) 1) tail))
@@ -0,0 +1 @@
+(open-input-file "./tests/no-such-file")
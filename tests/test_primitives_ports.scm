@@ -0,0 +1,10 @@
+(list (port? (current-input-port))
+      (input-port? (current-input-port))
+      (output-port? (current-output-port))
+      (output-port? (current-input-port))
+      (eof-object? (eof-object))
+      (eof-object? 'eof)
+      (eof-object? '|< END OF FILE >|)
+      (eof-object)
+      (current-input-port)
+      (current-error-port))
@@ -0,0 +1,4 @@
+(define port (open-output-string))
+(pretty-print '(define (my-map f lst) (if (null? lst) (quote ()) (cons (f (car lst)) (my-map f (cdr lst))))) port)
+(pretty-print '(a b c) port)
+(get-output-string port)
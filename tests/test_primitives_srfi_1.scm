@@ -0,0 +1,28 @@
+(define even? (lambda (n) (= n (* 2 (/ n 2)))))
+(define odd? (lambda (n) (not (even? n))))
+(define numbers (iota 6 1))
+(list numbers
+      (filter even? numbers)
+      (remove even? numbers)
+      (partition odd? numbers)
+      (reduce + 0 numbers)
+      (reduce + 0 '())
+      (fold cons '() '(1 2 3))
+      (fold (lambda (a b acc) (+ acc (* a b))) 0 '(1 2) '(3 4))
+      (fold-right cons '() '(1 2 3))
+      (delete 3 numbers)
+      (delete 3 numbers (lambda (x y) (< x y)))
+      (delete-duplicates '(a b a c b))
+      (iota 3 0 -2)
+      (find even? '(1 3 4 5))
+      (find-tail even? '(1 3 4 5))
+      (any (lambda (x) (if (even? x) x #f)) '(1 3 4 6))
+      (every odd? '(1 3 5))
+      (every odd? '())
+      (count < '(1 5 2) '(3 4 3))
+      (take numbers 2)
+      (drop numbers 4)
+      (split-at numbers 3)
+      (last numbers)
+      (append-map (lambda (x) (list x x)) '(1 2))
+      (filter-map (lambda (x) (if (odd? x) (* x 10) #f)) numbers))
@@ -0,0 +1,17 @@
+(define in (open-input-string "(foo bar) abc\nline"))
+(define datum (read in))
+(read-char in)
+(define a (peek-char in))
+(read-char in)
+(define bc (read-line in))
+(define line (read-line in))
+(define at-end (eof-object? (read-line in)))
+
+(define out (open-output-string))
+(write-string "x=" out)
+(write-char #\1 out)
+(newline out)
+
+(list datum a bc line at-end
+      (get-output-string out)
+      (with-output-to-string (lambda () (write-string "hi"))))
@@ -0,0 +1,9 @@
+(define datum (list "hi" #\x '|a b|))
+(define shared (list 1))
+(with-output-to-string
+  (lambda ()
+    (write datum)
+    (write-char #\space)
+    (display datum)
+    (write-char #\space)
+    (write-shared (list shared shared) (current-output-port))))
@@ -1,3 +1,4 @@
 (define cycle (cons 1 '()))
 (set-cdr! cycle cycle)
 (print cycle)
+(print (list cycle cycle))
@@ -0,0 +1,10 @@
+(define (ok) 1)
+
+(define x #\bogus)
+
+(display "fine")
+
+(define y (1 2 . 3 4))
+
+(define z 12x)
+(+ 1 2)